@@ -1,6 +1,7 @@
 use common::cli;
 use common::encoding;
 use common::error;
+use common::filelist::{self, Delimiter};
 use common::lexopt;
 use common::lexopt::prelude::*;
 use std::fs::File;
@@ -13,6 +14,7 @@ struct Opts {
     number: bool,
     raw: bool,
     files: Vec<String>,
+    from_list: bool,
 }
 
 fn parse_args() -> Opts {
@@ -20,6 +22,7 @@ fn parse_args() -> Opts {
         number: false,
         raw: false,
         files: Vec::new(),
+        from_list: false,
     };
     let mut parser = lexopt::Parser::from_env();
 
@@ -30,6 +33,16 @@ fn parse_args() -> Opts {
         match arg {
             Short('n') | Long("number") => opts.number = true,
             Long("raw") => opts.raw = true,
+            Long("files-from") => {
+                opts.files
+                    .extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Newline));
+                opts.from_list = true;
+            }
+            Long("files0-from") => {
+                opts.files
+                    .extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Nul));
+                opts.from_list = true;
+            }
             Long("help") => {
                 cli::print_help(TOOL, "concatenate files and print on the standard output");
                 std::process::exit(0);
@@ -81,7 +94,7 @@ fn run() -> io::Result<()> {
     let mut out = stdout.lock();
     let mut line_num: usize = 1;
 
-    if opts.files.is_empty() {
+    if opts.files.is_empty() && !opts.from_list {
         let reader = encoding::open_stdin()?;
         if opts.number {
             cat_numbered(reader, &mut line_num, &mut out)?;
@@ -5,3 +5,33 @@ pub fn print_help(tool_name: &str, usage: &str) {
 pub fn print_version(tool_name: &str, version: &str) {
     println!("{tool_name} {version}");
 }
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorChoice {
+    Always,
+    #[default]
+    Never,
+    Auto,
+}
+
+impl ColorChoice {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "always" | "yes" | "force" => Ok(ColorChoice::Always),
+            "never" | "no" | "none" => Ok(ColorChoice::Never),
+            "auto" | "tty" | "if-tty" => Ok(ColorChoice::Auto),
+            _ => Err(format!("invalid argument '{s}' for --color")),
+        }
+    }
+
+    pub fn enabled(self) -> bool {
+        use std::io::IsTerminal;
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use encoding_rs_io::DecodeReaderBytesBuilder;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Read};
 
 pub fn open_input(path: &str) -> io::Result<impl BufRead> {
     let file = File::open(path)?;
@@ -17,6 +17,14 @@ pub fn open_stdin() -> io::Result<impl BufRead> {
     Ok(BufReader::new(reader))
 }
 
+pub fn open_reader(reader: impl Read) -> impl BufRead {
+    BufReader::new(
+        DecodeReaderBytesBuilder::new()
+            .bom_sniffing(true)
+            .build(reader),
+    )
+}
+
 pub fn open_input_or_stdin(path: Option<&str>) -> io::Result<Box<dyn BufRead>> {
     match path {
         Some(p) => {
@@ -35,6 +43,50 @@ pub fn open_input_or_stdin(path: Option<&str>) -> io::Result<Box<dyn BufRead>> {
     }
 }
 
+/// Whether a block of text looks binary: it contains a NUL byte. Pass text
+/// read through this module, so UTF-16 (full of NUL bytes before decoding)
+/// is not mistaken for binary data.
+pub fn is_binary(decoded: &[u8]) -> bool {
+    decoded.contains(&0)
+}
+
+/// A byte-order mark, which `open_input` uses to pick the encoding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bom {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// The byte-order mark `bytes` starts with, if any.
+pub fn sniff_bom(bytes: &[u8]) -> Option<Bom> {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => Some(Bom::Utf8),
+        [0xFF, 0xFE, ..] => Some(Bom::Utf16Le),
+        [0xFE, 0xFF, ..] => Some(Bom::Utf16Be),
+        _ => None,
+    }
+}
+
+/// Encodes text read through this module back into the encoding of a file
+/// that started with `bom` (UTF-8 without one), writing the same BOM.
+pub fn encode(text: &str, bom: Option<Bom>) -> Vec<u8> {
+    match bom {
+        None => text.as_bytes().to_vec(),
+        Some(Bom::Utf8) => [&[0xEF, 0xBB, 0xBF], text.as_bytes()].concat(),
+        Some(Bom::Utf16Le) => [0xFEFF]
+            .into_iter()
+            .chain(text.encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect(),
+        Some(Bom::Utf16Be) => [0xFEFF]
+            .into_iter()
+            .chain(text.encode_utf16())
+            .flat_map(u16::to_be_bytes)
+            .collect(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,4 +137,37 @@ mod tests {
         let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
         assert_eq!(lines, vec!["line one", "line two"]);
     }
+
+    #[test]
+    fn binary_detection_after_decoding() {
+        let mut data = vec![0xFF, 0xFE];
+        data.extend("text\n".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        assert!(is_binary(&data));
+        let f = write_temp_file(&data);
+        let mut decoded = Vec::new();
+        open_input(f.path().to_str().unwrap())
+            .unwrap()
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert!(!is_binary(&decoded));
+        assert!(is_binary(b"ELF\0\x01"));
+    }
+
+    #[test]
+    fn encode_round_trips_bom_encodings() {
+        for bom in [
+            None,
+            Some(Bom::Utf8),
+            Some(Bom::Utf16Le),
+            Some(Bom::Utf16Be),
+        ] {
+            let bytes = encode("héllo\r\n", bom);
+            assert_eq!(sniff_bom(&bytes), bom);
+            let mut decoded = String::new();
+            open_reader(&bytes[..])
+                .read_to_string(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, "héllo\r\n");
+        }
+    }
 }
@@ -0,0 +1,92 @@
+use crate::{encoding, error};
+use std::io::{self, BufRead};
+
+/// How names are separated in a `--files-from` / `--files0-from` list.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delimiter {
+    Newline,
+    Nul,
+}
+
+/// Reads file names from `list` (`-` for stdin). Empty entries are skipped;
+/// with `Newline`, a trailing `\r` is stripped from each name.
+pub fn read(list: &str, delim: Delimiter) -> io::Result<Vec<String>> {
+    let reader = encoding::open_input_or_stdin((list != "-").then_some(list))?;
+    parse(reader, delim)
+}
+
+fn parse(mut reader: impl BufRead, delim: Delimiter) -> io::Result<Vec<String>> {
+    let sep = match delim {
+        Delimiter::Newline => b'\n',
+        Delimiter::Nul => 0,
+    };
+    let mut names = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(sep, &mut buf)? == 0 {
+            break;
+        }
+        let mut name: &[u8] = &buf;
+        name = name.strip_suffix(&[sep]).unwrap_or(name);
+        if delim == Delimiter::Newline {
+            name = name.strip_suffix(b"\r").unwrap_or(name);
+        }
+        if name.is_empty() {
+            continue;
+        }
+        let name = String::from_utf8(name.to_vec()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "invalid UTF-8 in file name")
+        })?;
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// Handles `--files-from=FILE` / `--files0-from=FILE` in a tool's argument
+/// loop, exiting with an error if the value or list cannot be read.
+pub fn from_arg(tool: &str, parser: &mut lexopt::Parser, delim: Delimiter) -> Vec<String> {
+    let list = parser
+        .value()
+        .unwrap_or_else(|e| error::err(tool, &e.to_string()))
+        .into_string()
+        .unwrap_or_else(|_| error::err(tool, "invalid UTF-8 in filename"));
+    read(&list, delim).unwrap_or_else(|e| error::err(tool, &format!("{list}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn newline_list_with_crlf_and_blanks() {
+        let names = parse(
+            Cursor::new("a.txt\r\n\r\nsub dir/b.txt\nc.txt"),
+            Delimiter::Newline,
+        );
+        assert_eq!(names.unwrap(), vec!["a.txt", "sub dir/b.txt", "c.txt"]);
+    }
+
+    #[test]
+    fn nul_list_keeps_newlines_in_names() {
+        let names = parse(Cursor::new("a\nb\0c.txt\0"), Delimiter::Nul);
+        assert_eq!(names.unwrap(), vec!["a\nb", "c.txt"]);
+    }
+
+    #[test]
+    fn utf16_list_is_transcoded() {
+        use std::io::Write;
+        let mut data = vec![0xFF, 0xFE];
+        data.extend(
+            "one.txt\r\ntwo.txt\r\n"
+                .encode_utf16()
+                .flat_map(|u| u.to_le_bytes()),
+        );
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        f.write_all(&data).unwrap();
+        f.flush().unwrap();
+        let names = read(f.path().to_str().unwrap(), Delimiter::Newline).unwrap();
+        assert_eq!(names, vec!["one.txt", "two.txt"]);
+    }
+}
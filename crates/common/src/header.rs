@@ -0,0 +1,192 @@
+use crate::cli::ColorChoice;
+use crate::error;
+use lexopt::Arg;
+use std::io::{self, Write};
+
+pub const STDIN_LABEL: &str = "standard input";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HeaderMode {
+    /// Headers only when there is more than one input.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Writes the `==> name <==` lines that separate inputs in head and tail.
+pub struct Header {
+    mode: HeaderMode,
+    format: Option<String>,
+    color: bool,
+    written: bool,
+}
+
+/// The header options head and tail share: `-q`, `-v`, `--header-format`
+/// and `--color`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeaderArg {
+    Quiet,
+    Verbose,
+    Format,
+    Color,
+}
+
+impl HeaderArg {
+    /// Recognizes a header option, so a tool can hand it to
+    /// [`HeaderOpts::apply`] before the borrow of its parser ends.
+    pub fn of(arg: &Arg) -> Option<Self> {
+        Some(match arg {
+            Arg::Short('q') | Arg::Long("quiet") | Arg::Long("silent") => HeaderArg::Quiet,
+            Arg::Short('v') | Arg::Long("verbose") => HeaderArg::Verbose,
+            Arg::Long("header-format") => HeaderArg::Format,
+            Arg::Long("color") | Arg::Long("colour") => HeaderArg::Color,
+            _ => return None,
+        })
+    }
+}
+
+/// Header settings collected from the command line.
+#[derive(Default)]
+pub struct HeaderOpts {
+    mode: HeaderMode,
+    format: Option<String>,
+    color: ColorChoice,
+}
+
+impl HeaderOpts {
+    /// Applies `arg`, reading its value from `parser` and exiting with an
+    /// error if the value is invalid.
+    pub fn apply(&mut self, tool: &str, arg: HeaderArg, parser: &mut lexopt::Parser) {
+        match arg {
+            HeaderArg::Quiet => self.mode = HeaderMode::Never,
+            HeaderArg::Verbose => self.mode = HeaderMode::Always,
+            HeaderArg::Format => {
+                self.format = Some(
+                    parser
+                        .value()
+                        .unwrap_or_else(|e| error::err(tool, &e.to_string()))
+                        .into_string()
+                        .unwrap_or_else(|_| error::err(tool, "invalid UTF-8 in header format")),
+                );
+            }
+            HeaderArg::Color => {
+                self.color = match parser.optional_value() {
+                    Some(v) => ColorChoice::parse(&v.to_string_lossy())
+                        .unwrap_or_else(|e| error::err(tool, &e)),
+                    None => ColorChoice::Auto,
+                };
+            }
+        }
+    }
+
+    pub fn header(self) -> Header {
+        Header::new(self.mode, self.format, self.color.enabled())
+    }
+}
+
+/// Name shown for an input path, with `-` meaning stdin.
+pub fn label(path: &str) -> &str {
+    if path == "-" {
+        STDIN_LABEL
+    } else {
+        path
+    }
+}
+
+impl Header {
+    pub fn new(mode: HeaderMode, format: Option<String>, color: bool) -> Self {
+        Header {
+            mode,
+            format,
+            color,
+            written: false,
+        }
+    }
+
+    pub fn enabled(&self, inputs: usize) -> bool {
+        match self.mode {
+            HeaderMode::Auto => inputs > 1,
+            HeaderMode::Always => true,
+            HeaderMode::Never => false,
+        }
+    }
+
+    /// Expands `{name}` in a `--header-format` template.
+    fn render(&self, name: &str) -> String {
+        match &self.format {
+            Some(f) => f.replace("{name}", name),
+            None => format!("==> {name} <=="),
+        }
+    }
+
+    /// Writes the header for `name`, preceded by a blank line unless it is
+    /// the first one.
+    pub fn write(&mut self, out: &mut impl Write, name: &str) -> io::Result<()> {
+        if self.written {
+            writeln!(out)?;
+        }
+        self.written = true;
+        let line = self.render(name);
+        if self.color {
+            writeln!(out, "\x1b[1m{line}\x1b[0m")
+        } else {
+            writeln!(out, "{line}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_all(header: &mut Header, names: &[&str]) -> String {
+        let mut out = Vec::new();
+        for name in names {
+            header.write(&mut out, name).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn default_format_with_separators() {
+        let mut h = Header::new(HeaderMode::Auto, None, false);
+        assert_eq!(render_all(&mut h, &["a", "b"]), "==> a <==\n\n==> b <==\n");
+    }
+
+    #[test]
+    fn custom_format() {
+        let mut h = Header::new(HeaderMode::Always, Some("# {name}".into()), false);
+        assert_eq!(render_all(&mut h, &[label("-")]), "# standard input\n");
+    }
+
+    #[test]
+    fn colored() {
+        let mut h = Header::new(HeaderMode::Always, None, true);
+        assert_eq!(render_all(&mut h, &["a"]), "\x1b[1m==> a <==\x1b[0m\n");
+    }
+
+    #[test]
+    fn header_args() {
+        let mut opts = HeaderOpts::default();
+        let mut parser = lexopt::Parser::from_args(["--header-format", "# {name}", "-q"]);
+        while let Some(arg) = parser.next().unwrap() {
+            let arg = HeaderArg::of(&arg).unwrap();
+            opts.apply("test", arg, &mut parser);
+        }
+        assert_eq!(opts.mode, HeaderMode::Never);
+        assert_eq!(opts.format.as_deref(), Some("# {name}"));
+        assert_eq!(opts.color, ColorChoice::Never);
+        assert_eq!(HeaderArg::of(&Arg::Long("colour")), Some(HeaderArg::Color));
+        assert_eq!(HeaderArg::of(&Arg::Short('n')), None);
+    }
+
+    #[test]
+    fn mode_controls_enabled() {
+        let auto = Header::new(HeaderMode::Auto, None, false);
+        assert!(!auto.enabled(1));
+        assert!(auto.enabled(2));
+        assert!(Header::new(HeaderMode::Always, None, false).enabled(1));
+        assert!(!Header::new(HeaderMode::Never, None, false).enabled(3));
+    }
+}
@@ -0,0 +1,171 @@
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+
+/// An opened input: random access for regular files whose bytes can be used
+/// as-is, streaming for pipes and inputs that need transcoding.
+pub enum Input {
+    Seekable(SeekableFile),
+    Stream(Box<dyn BufRead>),
+}
+
+impl Input {
+    pub fn into_reader(self) -> io::Result<Box<dyn BufRead>> {
+        match self {
+            Input::Seekable(f) => Ok(Box::new(f.reader_from(0)?)),
+            Input::Stream(r) => Ok(r),
+        }
+    }
+}
+
+/// A regular file with any UTF-8 BOM excluded from its addressable content.
+pub struct SeekableFile {
+    file: File,
+    start: u64,
+    len: u64,
+}
+
+impl SeekableFile {
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads into `buf` from `offset` bytes past the start of the content.
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let avail = self.len.saturating_sub(offset);
+        let want = buf.len().min(usize::try_from(avail).unwrap_or(usize::MAX));
+        self.file.seek(SeekFrom::Start(self.start + offset))?;
+        let mut filled = 0;
+        while filled < want {
+            match self.file.read(&mut buf[filled..want]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Returns a buffered reader positioned `offset` bytes into the content.
+    pub fn reader_from(mut self, offset: u64) -> io::Result<BufReader<File>> {
+        self.file
+            .seek(SeekFrom::Start(self.start + offset.min(self.len)))?;
+        Ok(BufReader::new(self.file))
+    }
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+fn is_utf16_bom(head: &[u8]) -> bool {
+    head.starts_with(&[0xFF, 0xFE]) || head.starts_with(&[0xFE, 0xFF])
+}
+
+pub fn open(path: &str) -> io::Result<Input> {
+    let mut file = File::open(path)?;
+    let meta = file.metadata()?;
+    if !meta.is_file() {
+        return Ok(Input::Stream(Box::new(BufReader::new(decoder(file)))));
+    }
+
+    let mut head = [0u8; 3];
+    let mut n = 0;
+    while n < head.len() {
+        match file.read(&mut head[n..])? {
+            0 => break,
+            k => n += k,
+        }
+    }
+    file.seek(SeekFrom::Start(0))?;
+
+    if is_utf16_bom(&head[..n]) {
+        return Ok(Input::Stream(Box::new(BufReader::new(decoder(file)))));
+    }
+    let start = if head[..n].starts_with(UTF8_BOM) {
+        UTF8_BOM.len() as u64
+    } else {
+        0
+    };
+    Ok(Input::Seekable(SeekableFile {
+        file,
+        start,
+        len: meta.len() - start,
+    }))
+}
+
+fn decoder(file: File) -> impl Read {
+    DecodeReaderBytesBuilder::new()
+        .bom_sniffing(true)
+        .build(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp_file(content: &[u8]) -> tempfile::NamedTempFile {
+        use std::io::Write;
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        f.write_all(content).unwrap();
+        f.flush().unwrap();
+        f
+    }
+
+    fn open_temp(f: &tempfile::NamedTempFile) -> Input {
+        open(f.path().to_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn plain_file_is_seekable() {
+        let f = write_temp_file(b"hello world\n");
+        let Input::Seekable(mut s) = open_temp(&f) else {
+            panic!("expected seekable input");
+        };
+        assert_eq!(s.len(), 12);
+        let mut buf = [0u8; 6];
+        assert_eq!(s.read_at(6, &mut buf).unwrap(), 6);
+        assert_eq!(&buf, b"world\n");
+    }
+
+    #[test]
+    fn utf8_bom_is_excluded() {
+        let f = write_temp_file(b"\xEF\xBB\xBFabc");
+        let Input::Seekable(mut s) = open_temp(&f) else {
+            panic!("expected seekable input");
+        };
+        assert_eq!(s.len(), 3);
+        let mut buf = [0u8; 8];
+        assert_eq!(s.read_at(0, &mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], b"abc");
+    }
+
+    #[test]
+    fn utf16_falls_back_to_stream() {
+        let mut data = vec![0xFF, 0xFE];
+        data.extend("hi\n".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        let f = write_temp_file(&data);
+        let input = open_temp(&f);
+        assert!(matches!(input, Input::Stream(_)));
+        let mut output = String::new();
+        input
+            .into_reader()
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        assert_eq!(output, "hi\n");
+    }
+
+    #[test]
+    fn read_past_end() {
+        let f = write_temp_file(b"abc");
+        let Input::Seekable(mut s) = open_temp(&f) else {
+            panic!("expected seekable input");
+        };
+        let mut buf = [0u8; 4];
+        assert_eq!(s.read_at(5, &mut buf).unwrap(), 0);
+    }
+}
@@ -0,0 +1,189 @@
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+// Signal number that interrupted the process, or 0.
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+#[cfg(unix)]
+mod sys {
+    use super::SIGNAL;
+    use std::sync::atomic::Ordering;
+
+    pub const SIGINT: i32 = libc::SIGINT;
+    const SIGTERM: i32 = libc::SIGTERM;
+
+    extern "C" fn handler(sig: libc::c_int) {
+        if SIGNAL.swap(sig, Ordering::SeqCst) != 0 {
+            // Second signal while shutting down: give up immediately.
+            unsafe { libc::_exit(128 + sig) };
+        }
+    }
+
+    fn set(sig: i32, action: libc::sighandler_t) {
+        // No SA_RESTART, so blocked reads and writes return EINTR and the
+        // tool gets a chance to notice the interruption.
+        unsafe {
+            let mut sa: libc::sigaction = std::mem::zeroed();
+            sa.sa_sigaction = action;
+            libc::sigemptyset(&mut sa.sa_mask);
+            libc::sigaction(sig, &sa, std::ptr::null_mut());
+        }
+    }
+
+    pub fn install() {
+        let h = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        set(SIGINT, h);
+        set(SIGTERM, h);
+    }
+
+    pub fn ignore_sigint() {
+        set(SIGINT, libc::SIG_IGN);
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use super::SIGNAL;
+    use std::sync::atomic::Ordering;
+
+    pub const SIGINT: i32 = 2;
+    const CTRL_C_EVENT: u32 = 0;
+    const CTRL_BREAK_EVENT: u32 = 1;
+
+    type HandlerRoutine = unsafe extern "system" fn(u32) -> i32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<HandlerRoutine>, add: i32) -> i32;
+    }
+
+    unsafe extern "system" fn handler(event: u32) -> i32 {
+        match event {
+            // Returning FALSE on a repeated event lets the default handler
+            // terminate the process.
+            CTRL_C_EVENT | CTRL_BREAK_EVENT => {
+                i32::from(SIGNAL.swap(SIGINT, Ordering::SeqCst) == 0)
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn install() {
+        unsafe {
+            SetConsoleCtrlHandler(Some(handler), 1);
+        }
+    }
+
+    pub fn ignore_sigint() {
+        unsafe {
+            SetConsoleCtrlHandler(None, 1);
+        }
+    }
+}
+
+/// Catches Ctrl-C (and SIGTERM on Unix) so the tool can finish writing
+/// what it has and exit with [`exit_code`]. A second signal exits at once.
+pub fn install() {
+    sys::install();
+}
+
+/// Ignores Ctrl-C / SIGINT for the rest of the process.
+pub fn ignore_sigint() {
+    sys::ignore_sigint();
+}
+
+pub fn interrupted() -> bool {
+    SIGNAL.load(Ordering::SeqCst) != 0
+}
+
+/// Conventional shell status for death by signal: 128 + signal number.
+pub fn exit_code() -> i32 {
+    match SIGNAL.load(Ordering::SeqCst) {
+        0 => 128 + sys::SIGINT,
+        sig => 128 + sig,
+    }
+}
+
+#[derive(Debug)]
+struct Interrupted;
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("interrupted")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+// Deliberately not ErrorKind::Interrupted: std's read/write loops retry on
+// that kind, which would swallow the interruption.
+fn error() -> io::Error {
+    io::Error::other(Interrupted)
+}
+
+pub fn is_interrupt(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|inner| inner.is::<Interrupted>())
+}
+
+pub fn check() -> io::Result<()> {
+    if interrupted() {
+        Err(error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Wraps a reader or writer so a blocked call that is woken by a signal
+/// fails with an interrupt error instead of silently retrying.
+pub struct Checked<T>(pub T);
+
+impl<R: Read> Read for Checked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            check()?;
+            match self.0.read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                r => return r,
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for Checked<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        loop {
+            check()?;
+            match self.0.write(buf) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                r => return r,
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupt_error_is_recognised() {
+        assert!(is_interrupt(&error()));
+        assert!(!is_interrupt(&io::Error::new(
+            io::ErrorKind::Interrupted,
+            "eintr"
+        )));
+        assert!(!is_interrupt(&io::Error::other("x")));
+    }
+
+    #[test]
+    fn checked_passes_data_through() {
+        let mut out = Vec::new();
+        let mut reader = Checked(&b"abc"[..]);
+        io::copy(&mut reader, &mut Checked(&mut out)).unwrap();
+        assert_eq!(out, b"abc");
+    }
+}
@@ -2,6 +2,14 @@ pub use lexopt;
 
 pub mod encoding;
 
+pub mod filelist;
+
+pub mod input;
+
+pub mod interrupt;
+
 pub mod cli;
 
 pub mod error;
+
+pub mod header;
@@ -0,0 +1,69 @@
+//! Compares grep's prefiltered search with the line-by-line path on a
+//! generated log. Run with `cargo bench -p grep --bench prefilter`.
+
+use std::io::Write;
+use std::time::Instant;
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("big.log");
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&log).unwrap());
+    for i in 0..1_000_000 {
+        write!(
+            file,
+            "2024-05-01T12:{:02}:{:02} [INFO] request {i} served for user{} in {}ms",
+            i % 60,
+            (i / 60) % 60,
+            i % 977,
+            i % 83
+        )
+        .unwrap();
+        if i % 10_000 == 0 {
+            write!(file, " slow-path id={i}").unwrap();
+        }
+        writeln!(file).unwrap();
+    }
+    file.flush().unwrap();
+    drop(file);
+
+    let grep = env!("CARGO_BIN_EXE_grep");
+    let log = log.to_str().unwrap();
+    let time = |args: &[&str]| {
+        let mut best = f64::MAX;
+        let mut out = String::new();
+        for _ in 0..3 {
+            let start = Instant::now();
+            let o = std::process::Command::new(grep)
+                .args(args)
+                .arg(log)
+                .output()
+                .unwrap();
+            best = best.min(start.elapsed().as_secs_f64());
+            out = String::from_utf8(o.stdout).unwrap();
+        }
+        (best, out)
+    };
+
+    println!("{grep}");
+    for (prefiltered, per_line) in [
+        (&["-c", "slow-path"][..], &["-c", r"\(slow-path\)"][..]),
+        (
+            &["-c", "-E", "id=[0-9]+0{4}"],
+            &["-c", "-E", "(id=)[0-9]+0{4}"],
+        ),
+        (&["-c", "-i", "SLOW-PATH"], &["-c", "-i", r"\(SLOW-PATH\)"]),
+        (
+            &["-n", "-B2", "slow-path"],
+            &["-n", "-B2", r"\(slow-path\)"],
+        ),
+    ] {
+        let (after, found) = time(prefiltered);
+        let (before, expected) = time(per_line);
+        assert_eq!(found, expected);
+        println!(
+            "{:<28} per line {before:>7.3}s  prefiltered {after:>7.3}s  ({:.1}x)",
+            prefiltered.join(" "),
+            before / after
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+
+/// Multi-literal matcher used for `-F` patterns: a byte trie with failure
+/// links, scanned once per haystack regardless of the number of patterns.
+pub struct AhoCorasick {
+    states: Vec<State>,
+    max_len: usize,
+    ignore_case: bool,
+}
+
+#[derive(Default)]
+struct State {
+    // Sorted by byte; most trie nodes have one or two children.
+    next: Vec<(u8, usize)>,
+    fail: usize,
+    // Length of the longest pattern ending at this state, following
+    // failure links.
+    out: Option<usize>,
+}
+
+const ROOT: usize = 0;
+
+impl State {
+    fn child(&self, byte: u8) -> Option<usize> {
+        self.next
+            .binary_search_by_key(&byte, |&(b, _)| b)
+            .ok()
+            .map(|i| self.next[i].1)
+    }
+}
+
+impl AhoCorasick {
+    pub fn new<S: AsRef<str>>(patterns: &[S], ignore_case: bool) -> Self {
+        let mut states = vec![State::default()];
+        let mut max_len = 0;
+
+        for pattern in patterns {
+            let pattern = pattern.as_ref().as_bytes();
+            max_len = max_len.max(pattern.len());
+            let mut cur = ROOT;
+            for &b in pattern {
+                let b = fold(b, ignore_case);
+                cur = match states[cur].child(b) {
+                    Some(s) => s,
+                    None => {
+                        let id = states.len();
+                        states.push(State::default());
+                        let pos = states[cur].next.partition_point(|&(c, _)| c < b);
+                        states[cur].next.insert(pos, (b, id));
+                        id
+                    }
+                };
+            }
+            states[cur].out = Some(pattern.len());
+        }
+
+        // Breadth-first, so a state's failure target is finished before it.
+        let mut queue = VecDeque::new();
+        let root_out = states[ROOT].out;
+        for (_, s) in states[ROOT].next.clone() {
+            states[s].out = states[s].out.max(root_out);
+            queue.push_back(s);
+        }
+        while let Some(cur) = queue.pop_front() {
+            for (b, child) in states[cur].next.clone() {
+                let mut f = states[cur].fail;
+                let fail = loop {
+                    if let Some(s) = states[f].child(b) {
+                        break s;
+                    }
+                    if f == ROOT {
+                        break ROOT;
+                    }
+                    f = states[f].fail;
+                };
+                states[child].fail = fail;
+                states[child].out = states[child].out.max(states[fail].out);
+                queue.push_back(child);
+            }
+        }
+
+        AhoCorasick {
+            states,
+            max_len,
+            ignore_case,
+        }
+    }
+
+    fn step(&self, mut cur: usize, byte: u8) -> usize {
+        loop {
+            if let Some(s) = self.states[cur].child(byte) {
+                return s;
+            }
+            if cur == ROOT {
+                return ROOT;
+            }
+            cur = self.states[cur].fail;
+        }
+    }
+
+    /// Finds the leftmost match starting at or after `at`, preferring the
+    /// longest pattern among those starting at the same position (as GNU
+    /// grep does for `-F -o`).
+    pub fn find_at(&self, haystack: &str, at: usize) -> Option<(usize, usize)> {
+        self.find_bytes(haystack.as_bytes(), at)
+    }
+
+    /// `find_at` over bytes that need not be UTF-8.
+    pub fn find_bytes(&self, bytes: &[u8], at: usize) -> Option<(usize, usize)> {
+        let mut best = self.states[ROOT].out.map(|_| (at, at));
+        let mut cur = ROOT;
+
+        for (i, &b) in bytes.iter().enumerate().skip(at) {
+            let end = i + 1;
+            if best.is_some_and(|(start, _)| end - start > self.max_len) {
+                break;
+            }
+            cur = self.step(cur, fold(b, self.ignore_case));
+            if let Some(len) = self.states[cur].out {
+                let start = end - len;
+                match best {
+                    Some((s, e)) if s < start || (s == start && e >= end) => {}
+                    _ => best = Some((start, end)),
+                }
+            }
+        }
+        best
+    }
+}
+
+fn fold(b: u8, ignore_case: bool) -> u8 {
+    if ignore_case {
+        b.to_ascii_lowercase()
+    } else {
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(patterns: &[&str], haystack: &str) -> Option<(usize, usize)> {
+        AhoCorasick::new(patterns, false).find_at(haystack, 0)
+    }
+
+    #[test]
+    fn finds_single_literal() {
+        assert_eq!(find(&["a.b[0]"], "x = a.b[0];"), Some((4, 10)));
+        assert_eq!(find(&["a.b[0]"], "x = axb[0];"), None);
+    }
+
+    #[test]
+    fn leftmost_then_longest() {
+        assert_eq!(find(&["bcd", "abc"], "xabcd"), Some((1, 4)));
+        assert_eq!(find(&["ab", "abcd"], "abcde"), Some((0, 4)));
+        assert_eq!(find(&["abcd", "bc"], "abce"), Some((1, 3)));
+    }
+
+    #[test]
+    fn failure_links() {
+        assert_eq!(find(&["he", "she", "hers"], "ushers"), Some((1, 4)));
+        assert_eq!(find(&["aab"], "aaab"), Some((1, 4)));
+    }
+
+    #[test]
+    fn empty_pattern_matches_everywhere() {
+        assert_eq!(find(&[""], "abc"), Some((0, 0)));
+        assert_eq!(find(&["", "ab"], "abc"), Some((0, 2)));
+    }
+
+    #[test]
+    fn no_patterns_never_match() {
+        let ac = AhoCorasick::new::<&str>(&[], false);
+        assert_eq!(ac.find_at("abc", 0), None);
+        assert_eq!(ac.find_at("", 0), None);
+    }
+
+    #[test]
+    fn ascii_case_folding() {
+        let ac = AhoCorasick::new(&["Hello"], true);
+        assert_eq!(ac.find_at("say HELLO", 0), Some((4, 9)));
+        assert_eq!(ac.find_at("hello hello", 1), Some((6, 11)));
+    }
+}
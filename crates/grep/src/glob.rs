@@ -0,0 +1,270 @@
+/// A shell wildcard pattern as used by `--include`/`--exclude`: `*` matches
+/// any run of characters, `?` any one character, `[...]` a set (`[!...]` or
+/// `[^...]` to negate) and `\` escapes the next character. Matching ignores
+/// case on Windows, like the file system does.
+#[derive(Debug)]
+pub struct Glob {
+    tokens: Vec<Token>,
+    path: bool,
+}
+
+#[derive(Debug)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    // `**` ending a path pattern: `a/**` matches everything below `a`.
+    AnyPath,
+    // `**/`: zero or more leading directories.
+    Dirs,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        Self::parse(pattern, false)
+    }
+
+    /// A glob over `/`-separated paths, as in `.gitignore`: `*`, `?` and
+    /// sets never match `/`, and `**` spans directories.
+    pub fn new_path(pattern: &str) -> Self {
+        Self::parse(pattern, true)
+    }
+
+    fn parse(pattern: &str, path: bool) -> Self {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars();
+        let mut prev: Option<char> = None;
+        while let Some(c) = chars.next() {
+            let component_start = matches!(prev, None | Some('/'));
+            prev = Some(c);
+            let token = match c {
+                '*' if path && component_start && chars.as_str().starts_with('*') => {
+                    chars.next();
+                    let rest = chars.as_str();
+                    if rest.is_empty() {
+                        Token::AnyPath
+                    } else if rest.starts_with('/') {
+                        chars.next();
+                        prev = Some('/');
+                        Token::Dirs
+                    } else {
+                        Token::Star
+                    }
+                }
+                '*' => {
+                    if matches!(tokens.last(), Some(Token::Star)) {
+                        continue;
+                    }
+                    Token::Star
+                }
+                '?' => Token::Any,
+                '\\' => Token::Char(chars.next().unwrap_or('\\')),
+                '[' => match parse_class(&mut chars.clone()) {
+                    Some((token, used)) => {
+                        for _ in 0..used {
+                            chars.next();
+                        }
+                        token
+                    }
+                    // An unterminated `[` is an ordinary character.
+                    None => Token::Char('['),
+                },
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+        Glob { tokens, path }
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        let name: Vec<char> = name.chars().collect();
+        self.match_here(&self.tokens, &name)
+    }
+
+    fn match_here(&self, tokens: &[Token], name: &[char]) -> bool {
+        let Some((token, rest)) = tokens.split_first() else {
+            return name.is_empty();
+        };
+        match token {
+            Token::Star => {
+                for i in 0..=name.len() {
+                    if self.match_here(rest, &name[i..]) {
+                        return true;
+                    }
+                    if self.path && name.get(i) == Some(&'/') {
+                        break;
+                    }
+                }
+                false
+            }
+            Token::AnyPath => (0..=name.len()).any(|i| self.match_here(rest, &name[i..])),
+            Token::Dirs => {
+                self.match_here(rest, name)
+                    || (0..name.len())
+                        .any(|i| name[i] == '/' && self.match_here(rest, &name[i + 1..]))
+            }
+            token => match name.split_first() {
+                Some((&c, tail)) => {
+                    let crosses = self.path && c == '/' && !matches!(token, Token::Char(_));
+                    !crosses && token.matches(c) && self.match_here(rest, tail)
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(p) => eq(*p, c),
+            Token::Any => true,
+            Token::Star | Token::AnyPath | Token::Dirs => false,
+            Token::Class { negated, ranges } => {
+                let in_set = |c: char| ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c));
+                let found = in_set(c)
+                    || (cfg!(windows)
+                        && (in_set(c.to_ascii_lowercase()) || in_set(c.to_ascii_uppercase())));
+                found != *negated
+            }
+        }
+    }
+}
+
+fn eq(a: char, b: char) -> bool {
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(&b)
+    } else {
+        a == b
+    }
+}
+
+/// Parses the rest of a `[...]` set, returning the token and the number of
+/// characters consumed after the `[`.
+fn parse_class(chars: &mut impl Iterator<Item = char>) -> Option<(Token, usize)> {
+    let mut used = 0;
+    let mut next = || {
+        used += 1;
+        chars.next()
+    };
+    let mut ranges = Vec::new();
+    let mut c = next()?;
+    let negated = c == '!' || c == '^';
+    if negated {
+        c = next()?;
+    }
+    // A `]` first in the set is literal.
+    let mut first = true;
+    loop {
+        if c == ']' && !first {
+            break;
+        }
+        first = false;
+        let lo = if c == '\\' { next()? } else { c };
+        c = next()?;
+        if c == '-' {
+            let hi = next()?;
+            if hi == ']' {
+                ranges.push((lo, lo));
+                ranges.push(('-', '-'));
+                break;
+            }
+            let hi = if hi == '\\' { next()? } else { hi };
+            ranges.push((lo, hi));
+            c = next()?;
+        } else {
+            ranges.push((lo, lo));
+        }
+    }
+    Some((Token::Class { negated, ranges }, used))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(pattern: &str, name: &str) -> bool {
+        Glob::new(pattern).is_match(name)
+    }
+
+    #[test]
+    fn literal_and_wildcards() {
+        assert!(m("main.rs", "main.rs"));
+        assert!(!m("main.rs", "main.rsx"));
+        assert!(m("*.rs", "main.rs"));
+        assert!(m("*.rs", ".rs"));
+        assert!(!m("*.rs", "main.rc"));
+        assert!(m("a?c", "abc"));
+        assert!(!m("a?c", "ac"));
+        assert!(m("*", ""));
+        assert!(m("a*b*c", "axxbyyc"));
+        assert!(!m("a*b*c", "axxbyy"));
+        assert!(m("**.min.js", "app.min.js"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(m("[abc].txt", "b.txt"));
+        assert!(!m("[abc].txt", "d.txt"));
+        assert!(m("[!abc].txt", "d.txt"));
+        assert!(m("[^abc].txt", "d.txt"));
+        assert!(m("file[0-9]", "file7"));
+        assert!(!m("file[0-9]", "filex"));
+        assert!(m("[]]", "]"));
+        assert!(m("[a-]", "-"));
+        assert!(m("[", "["));
+        assert!(m("a[", "a["));
+    }
+
+    #[test]
+    fn escapes() {
+        assert!(m("\\*.txt", "*.txt"));
+        assert!(!m("\\*.txt", "a.txt"));
+        assert!(m("[\\]]", "]"));
+    }
+
+    #[test]
+    fn non_ascii_names() {
+        assert!(m("caf?.txt", "café.txt"));
+        assert!(m("*é*", "résumé"));
+    }
+
+    fn p(pattern: &str, name: &str) -> bool {
+        Glob::new_path(pattern).is_match(name)
+    }
+
+    #[test]
+    fn path_wildcards_stop_at_separators() {
+        assert!(p("src/*.rs", "src/main.rs"));
+        assert!(!p("src/*.rs", "src/bin/main.rs"));
+        assert!(!p("a?b", "a/b"));
+        assert!(!p("a[/]b", "a/b"));
+        assert!(m("src/*.rs", "src/bin/main.rs"));
+    }
+
+    #[test]
+    fn double_star() {
+        assert!(p("**/foo", "foo"));
+        assert!(p("**/foo", "a/b/foo"));
+        assert!(!p("**/foo", "a/xfoo"));
+        assert!(p("a/**", "a/b"));
+        assert!(p("a/**", "a/b/c"));
+        assert!(!p("a/**", "b/c"));
+        assert!(p("a/**/b", "a/b"));
+        assert!(p("a/**/b", "a/x/y/b"));
+        assert!(!p("a/**/b", "a/x/yb"));
+        assert!(p("a**b", "axxb"));
+        assert!(!p("a**b", "ax/xb"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn case_insensitive_on_windows() {
+        assert!(m("*.RS", "main.rs"));
+        assert!(m("[A-C]x", "bx"));
+    }
+}
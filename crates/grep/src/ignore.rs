@@ -0,0 +1,299 @@
+use crate::glob::Glob;
+use common::encoding;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// One line of a `.gitignore`-style file.
+struct Rule {
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+    // Patterns containing a `/` match the path relative to the ignore
+    // file's directory; others match the entry's name at any depth.
+    anchored: bool,
+}
+
+/// The rules from one ignore file. `depth` is the number of path components
+/// between the walk's base and the directory the rules are relative to.
+pub struct IgnoreFile {
+    rules: Vec<Rule>,
+    depth: usize,
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    // Trailing spaces are ignored unless escaped with a backslash.
+    let mut pattern = line.trim_end_matches(' ');
+    if pattern.ends_with('\\') && pattern.len() < line.len() {
+        pattern = &line[..pattern.len() + 1];
+    }
+    let (negated, pattern) = match pattern.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let anchored = pattern.contains('/');
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    if pattern.is_empty() {
+        return None;
+    }
+    Some(Rule {
+        glob: Glob::new_path(pattern),
+        negated,
+        dir_only,
+        anchored,
+    })
+}
+
+impl IgnoreFile {
+    pub fn parse(text: &str, depth: usize) -> Self {
+        IgnoreFile {
+            rules: text.lines().filter_map(parse_rule).collect(),
+            depth,
+        }
+    }
+
+    /// Loads `path` if it exists and has any rules.
+    pub fn load(path: &Path, depth: usize) -> Option<Self> {
+        let mut text = String::new();
+        encoding::open_input(path.to_str()?)
+            .ok()?
+            .read_to_string(&mut text)
+            .ok()?;
+        let file = Self::parse(&text, depth);
+        (!file.rules.is_empty()).then_some(file)
+    }
+
+    /// `Some(true)` if the entry at `components` (relative to the walk's
+    /// base) is ignored, `Some(false)` if a `!` rule re-includes it, and
+    /// `None` if no rule mentions it. The last matching rule wins.
+    fn matched(&self, components: &[String], is_dir: bool) -> Option<bool> {
+        let rel = components.get(self.depth..)?;
+        let name = rel.last()?;
+        let path = rel.join("/");
+        self.rules
+            .iter()
+            .rev()
+            .filter(|r| is_dir || !r.dir_only)
+            .find(|r| r.glob.is_match(if r.anchored { &path } else { name }))
+            .map(|r| !r.negated)
+    }
+}
+
+/// Ignore files in effect, lowest precedence first: global excludes,
+/// `.git/info/exclude`, then each directory's `.gitignore` and `.ignore`
+/// from the repository root down.
+#[derive(Default)]
+pub struct Ignores {
+    files: Vec<IgnoreFile>,
+}
+
+impl Ignores {
+    pub fn is_ignored(&self, components: &[String], is_dir: bool) -> bool {
+        self.files
+            .iter()
+            .rev()
+            .find_map(|f| f.matched(components, is_dir))
+            .unwrap_or(false)
+    }
+
+    /// Adds the ignore files found in `dir`, returning how many were added
+    /// so the caller can pop them when leaving the directory.
+    pub fn enter(&mut self, dir: &Path, depth: usize, in_repo: bool) -> usize {
+        let before = self.files.len();
+        if in_repo {
+            self.files
+                .extend(IgnoreFile::load(&dir.join(".gitignore"), depth));
+        }
+        self.files
+            .extend(IgnoreFile::load(&dir.join(".ignore"), depth));
+        self.files.len() - before
+    }
+
+    pub fn leave(&mut self, count: usize) {
+        self.files.truncate(self.files.len() - count);
+    }
+
+    /// Sets up the ignore rules for walking `root`: repository-wide
+    /// excludes and the ignore files of directories between the repository
+    /// root and `root`. Returns the path components of `root` relative to
+    /// the repository root (empty outside a repository) and whether `root`
+    /// is inside one.
+    pub fn for_root(root: &Path) -> (Self, Vec<String>, bool) {
+        let mut ignores = Ignores::default();
+        let Ok(abs) = fs::canonicalize(root) else {
+            return (ignores, Vec::new(), false);
+        };
+        let Some(repo) = abs.ancestors().find(|d| d.join(".git").exists()) else {
+            return (ignores, Vec::new(), false);
+        };
+        let components: Vec<String> = abs
+            .strip_prefix(repo)
+            .unwrap_or(Path::new(""))
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+
+        let git_dir = git_dir(repo);
+        if let Some(global) = global_excludes_path(git_dir.as_deref()) {
+            ignores.files.extend(IgnoreFile::load(&global, 0));
+        }
+        if let Some(git_dir) = &git_dir {
+            ignores
+                .files
+                .extend(IgnoreFile::load(&git_dir.join("info").join("exclude"), 0));
+        }
+        let mut dir = repo.to_path_buf();
+        for (depth, component) in components.iter().enumerate() {
+            ignores.enter(&dir, depth, true);
+            dir.push(component);
+        }
+        (ignores, components, true)
+    }
+}
+
+/// The repository's git directory: `.git` itself, or the target of a
+/// `gitdir:` file as used by worktrees and submodules.
+fn git_dir(repo: &Path) -> Option<PathBuf> {
+    let dot_git = repo.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let text = fs::read_to_string(&dot_git).ok()?;
+    let target = text.trim().strip_prefix("gitdir:")?.trim();
+    Some(repo.join(target))
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn xdg_git_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join("git")),
+        _ => home_dir().map(|h| h.join(".config").join("git")),
+    }
+}
+
+/// Extracts `core.excludesFile` from git config text.
+fn config_excludes_file(text: &str) -> Option<String> {
+    let mut in_core = false;
+    let mut found = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_core = line
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .trim()
+                .eq_ignore_ascii_case("core");
+        } else if let (true, Some((key, value))) = (in_core, line.split_once('=')) {
+            if key.trim().eq_ignore_ascii_case("excludesfile") {
+                found = Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    found
+}
+
+/// `core.excludesFile` from the repository, global or XDG git config (in
+/// that order of precedence), defaulting to `$XDG_CONFIG_HOME/git/ignore`.
+fn global_excludes_path(git_dir: Option<&Path>) -> Option<PathBuf> {
+    let configs = [
+        git_dir.map(|d| d.join("config")),
+        home_dir().map(|h| h.join(".gitconfig")),
+        xdg_git_dir().map(|d| d.join("config")),
+    ];
+    let configured = configs
+        .iter()
+        .flatten()
+        .find_map(|path| config_excludes_file(&fs::read_to_string(path).ok()?));
+    match configured {
+        Some(path) => match path.strip_prefix("~/") {
+            Some(rest) => home_dir().map(|h| h.join(rest)),
+            None => Some(PathBuf::from(path)),
+        },
+        None => xdg_git_dir().map(|d| d.join("ignore")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(path: &str) -> Vec<String> {
+        path.split('/').map(str::to_string).collect()
+    }
+
+    fn ignores(files: &[(&str, usize)]) -> Ignores {
+        Ignores {
+            files: files
+                .iter()
+                .map(|(text, depth)| IgnoreFile::parse(text, *depth))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn unanchored_patterns_match_at_any_depth() {
+        let ig = ignores(&[("*.log\ntarget/\n", 0)]);
+        assert!(ig.is_ignored(&parts("a.log"), false));
+        assert!(ig.is_ignored(&parts("src/deep/b.log"), false));
+        assert!(ig.is_ignored(&parts("sub/target"), true));
+        assert!(!ig.is_ignored(&parts("sub/target"), false));
+        assert!(!ig.is_ignored(&parts("src/main.rs"), false));
+    }
+
+    #[test]
+    fn anchored_patterns_are_relative_to_the_file() {
+        let ig = ignores(&[("/build\ndoc/*.html\n", 0), ("/gen\n", 1)]);
+        assert!(ig.is_ignored(&parts("build"), true));
+        assert!(!ig.is_ignored(&parts("src/build"), true));
+        assert!(ig.is_ignored(&parts("doc/a.html"), false));
+        assert!(!ig.is_ignored(&parts("doc/x/a.html"), false));
+        assert!(ig.is_ignored(&parts("src/gen"), true));
+        assert!(!ig.is_ignored(&parts("gen"), true));
+    }
+
+    #[test]
+    fn negation_and_precedence() {
+        let ig = ignores(&[("*.log\n!keep.log\n", 0)]);
+        assert!(ig.is_ignored(&parts("a.log"), false));
+        assert!(!ig.is_ignored(&parts("keep.log"), false));
+
+        // A deeper file overrides a shallower one.
+        let ig = ignores(&[("*.txt\n", 0), ("!notes.txt\n", 1)]);
+        assert!(!ig.is_ignored(&parts("docs/notes.txt"), false));
+        assert!(ig.is_ignored(&parts("notes.txt"), false));
+    }
+
+    #[test]
+    fn comments_escapes_and_spaces() {
+        let ig = ignores(&[("# comment\n\\#hash\n\\!bang\ntrail   \nesc\\ \n", 0)]);
+        assert!(!ig.is_ignored(&parts("# comment"), false));
+        assert!(ig.is_ignored(&parts("#hash"), false));
+        assert!(ig.is_ignored(&parts("!bang"), false));
+        assert!(ig.is_ignored(&parts("trail"), false));
+        assert!(ig.is_ignored(&parts("esc "), false));
+    }
+
+    #[test]
+    fn excludes_file_from_config() {
+        let text = "[user]\n\tname = x\n[core]\n\texcludesFile = \"~/.gitignore_global\"\n";
+        assert_eq!(
+            config_excludes_file(text).as_deref(),
+            Some("~/.gitignore_global")
+        );
+        assert_eq!(config_excludes_file("[other]\nexcludesfile = x\n"), None);
+    }
+}
@@ -0,0 +1,297 @@
+use std::sync::OnceLock;
+
+/// Decompresses a raw DEFLATE stream (RFC 1951), as stored in zip entries.
+/// Fails if the output would grow past `limit` bytes, so a corrupt or
+/// hostile size can't exhaust memory.
+pub fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut state = Inflater {
+        data,
+        pos: 0,
+        bitbuf: 0,
+        bitcnt: 0,
+        out: Vec::with_capacity(limit.min(1 << 24)),
+        limit,
+    };
+    loop {
+        let last = state.bits(1)? == 1;
+        match state.bits(2)? {
+            0 => state.stored()?,
+            1 => {
+                let (lencode, distcode) = fixed_tables();
+                state.codes(lencode, distcode)?;
+            }
+            2 => {
+                let (lencode, distcode) = state.dynamic_tables()?;
+                state.codes(&lencode, &distcode)?;
+            }
+            _ => return Err("invalid block type".to_string()),
+        }
+        if last {
+            return Ok(state.out);
+        }
+    }
+}
+
+const MAX_BITS: usize = 15;
+
+/// A canonical Huffman code: how many codes there are of each length, and
+/// the symbols in code order.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, String> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+        // Incomplete codes are allowed (a lone distance code is), but not
+        // ones with more codes of a length than fit.
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err("invalid Huffman code lengths".to_string());
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                let slot = &mut offsets[usize::from(len)];
+                symbols[usize::from(*slot)] = symbol as u16;
+                *slot += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order code length code lengths are sent in.
+const CODE_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+static FIXED: OnceLock<(Huffman, Huffman)> = OnceLock::new();
+
+/// The fixed literal/length and distance codes of block type 1.
+fn fixed_tables() -> &'static (Huffman, Huffman) {
+    FIXED.get_or_init(|| {
+        let mut lengths = [8u8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        let lencode = Huffman::new(&lengths).expect("fixed code is valid");
+        let distcode = Huffman::new(&[5; 30]).expect("fixed code is valid");
+        (lencode, distcode)
+    })
+}
+
+struct Inflater<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bitbuf: u32,
+    bitcnt: u32,
+    out: Vec<u8>,
+    limit: usize,
+}
+
+impl Inflater<'_> {
+    /// The next `need` bits of input, least significant first.
+    fn bits(&mut self, need: u32) -> Result<u32, String> {
+        let mut val = self.bitbuf;
+        while self.bitcnt < need {
+            let &byte = self.data.get(self.pos).ok_or("unexpected end of data")?;
+            self.pos += 1;
+            val |= u32::from(byte) << self.bitcnt;
+            self.bitcnt += 8;
+        }
+        self.bitbuf = val >> need;
+        self.bitcnt -= need;
+        Ok(val & ((1 << need) - 1))
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Result<(), String> {
+        if self.out.len() + bytes.len() > self.limit {
+            return Err("data is larger than its recorded size".to_string());
+        }
+        self.out.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn stored(&mut self) -> Result<(), String> {
+        // Stored blocks start on a byte boundary.
+        self.bitbuf = 0;
+        self.bitcnt = 0;
+        let header = self
+            .data
+            .get(self.pos..self.pos + 4)
+            .ok_or("unexpected end of data")?;
+        let len = u16::from_le_bytes([header[0], header[1]]);
+        let nlen = u16::from_le_bytes([header[2], header[3]]);
+        if len != !nlen {
+            return Err("invalid stored block length".to_string());
+        }
+        self.pos += 4;
+        let block = self
+            .data
+            .get(self.pos..self.pos + usize::from(len))
+            .ok_or("unexpected end of data")?;
+        self.pos += block.len();
+        self.push(block)
+    }
+
+    fn decode(&mut self, h: &Huffman) -> Result<u16, String> {
+        // Canonical codes of each length follow the codes of the length
+        // before, so one bit at a time walks them in order.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &h.counts[1..] {
+            code |= self.bits(1)? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(h.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+
+    fn codes(&mut self, lencode: &Huffman, distcode: &Huffman) -> Result<(), String> {
+        loop {
+            let symbol = self.decode(lencode)?;
+            match symbol {
+                0..=255 => self.push(&[symbol as u8])?,
+                256 => return Ok(()),
+                _ => {
+                    let i = usize::from(symbol - 257);
+                    if i >= LENGTH_BASE.len() {
+                        return Err("invalid length code".to_string());
+                    }
+                    let len = usize::from(LENGTH_BASE[i])
+                        + self.bits(u32::from(LENGTH_EXTRA[i]))? as usize;
+                    let i = usize::from(self.decode(distcode)?);
+                    if i >= DIST_BASE.len() {
+                        return Err("invalid distance code".to_string());
+                    }
+                    let dist =
+                        usize::from(DIST_BASE[i]) + self.bits(u32::from(DIST_EXTRA[i]))? as usize;
+                    if dist > self.out.len() {
+                        return Err("distance too far back".to_string());
+                    }
+                    if self.out.len() + len > self.limit {
+                        return Err("data is larger than its recorded size".to_string());
+                    }
+                    // The copy may overlap what it writes, so go byte by byte.
+                    let start = self.out.len() - dist;
+                    for k in 0..len {
+                        let byte = self.out[start + k];
+                        self.out.push(byte);
+                    }
+                }
+            }
+        }
+    }
+
+    fn dynamic_tables(&mut self) -> Result<(Huffman, Huffman), String> {
+        let nlen = self.bits(5)? as usize + 257;
+        let ndist = self.bits(5)? as usize + 1;
+        let ncode = self.bits(4)? as usize + 4;
+        if nlen > 286 || ndist > 30 {
+            return Err("invalid code counts".to_string());
+        }
+        let mut lengths = [0u8; 19];
+        for &slot in &CODE_ORDER[..ncode] {
+            lengths[slot] = self.bits(3)? as u8;
+        }
+        let lencode = Huffman::new(&lengths)?;
+
+        let mut lengths = [0u8; 320];
+        let mut i = 0;
+        while i < nlen + ndist {
+            let symbol = self.decode(&lencode)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let &previous = i
+                        .checked_sub(1)
+                        .and_then(|p| lengths.get(p))
+                        .ok_or("repeat with no previous length")?;
+                    (previous, 3 + self.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits(3)? as usize),
+                _ => (0, 11 + self.bits(7)? as usize),
+            };
+            if i + repeat > nlen + ndist {
+                return Err("too many code lengths".to_string());
+            }
+            lengths[i..i + repeat].fill(value);
+            i += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err("no end-of-block code".to_string());
+        }
+        Ok((
+            Huffman::new(&lengths[..nlen])?,
+            Huffman::new(&lengths[nlen..nlen + ndist])?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Streams from zlib, one per block type.
+    const STORED: &[u8] = &[
+        0x01, 0x0c, 0x00, 0xf3, 0xff, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x64, 0x20, 0x74, 0x65, 0x78,
+        0x74, 0x0a,
+    ];
+    const FIXED_CODES: &[u8] = &[
+        0x4b, 0x4c, 0x4a, 0x4e, 0x84, 0x21, 0x85, 0xb4, 0xcc, 0x8a, 0xd4, 0x14, 0x2e, 0x00,
+    ];
+    const DYNAMIC_CODES: &[u8] = &[
+        0x15, 0xc8, 0x31, 0x0a, 0x00, 0x30, 0x08, 0x04, 0xc1, 0xde, 0x57, 0xe4, 0x6b, 0x16, 0x0b,
+        0xda, 0x9c, 0x60, 0xee, 0xff, 0x84, 0x4c, 0x39, 0x2c, 0x46, 0xad, 0x48, 0x10, 0x39, 0x87,
+        0xb6, 0xff, 0x41, 0x14, 0xc9, 0x70, 0xdd, 0x2a, 0x3f,
+    ];
+
+    #[test]
+    fn block_types() {
+        assert_eq!(inflate(STORED, 100).unwrap(), b"stored text\n");
+        assert_eq!(inflate(FIXED_CODES, 100).unwrap(), b"abcabcabcabc fixed\n");
+        assert_eq!(
+            inflate(DYNAMIC_CODES, 100).unwrap(),
+            b"eretenin\naeeneao eittreteee\nheaeoestinht"
+        );
+    }
+
+    #[test]
+    fn bad_streams_are_errors() {
+        assert!(inflate(FIXED_CODES, 10).is_err());
+        assert!(inflate(&DYNAMIC_CODES[..20], 100).is_err());
+        assert!(inflate(&[0x07], 100).is_err());
+        assert!(inflate(&[0x01, 0x0c, 0x00, 0xf3, 0xfe], 100).is_err());
+    }
+}
@@ -1,45 +1,294 @@
+mod aho_corasick;
+mod glob;
+mod ignore;
+mod inflate;
+mod matcher;
+mod prefilter;
+mod replace;
+mod stats;
+mod syntax;
+mod types;
+mod walk;
+mod zip;
+
+use common::filelist::{self, Delimiter};
 use common::{cli, encoding, error};
 use lexopt::prelude::*;
-use regex_lite::{Regex, RegexBuilder};
-use std::fs;
-use std::io::{self, BufRead, Write};
+use matcher::Matcher;
+use prefilter::Prefilter;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufRead, Read, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Instant;
 
-const TOOL: &str = "grep";
+pub(crate) const TOOL: &str = "grep";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryFiles {
+    Binary,
+    Text,
+    WithoutMatch,
+}
+
+impl BinaryFiles {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "binary" => Ok(BinaryFiles::Binary),
+            "text" => Ok(BinaryFiles::Text),
+            "without-match" => Ok(BinaryFiles::WithoutMatch),
+            _ => Err(format!(
+                "invalid argument '{s}' for --binary-files \
+                 (expected 'binary', 'text' or 'without-match')"
+            )),
+        }
+    }
+}
+
 struct Opts {
-    pattern: String,
+    patterns: Vec<String>,
+    fixed_strings: bool,
+    syntax: syntax::Syntax,
+    word_regexp: bool,
+    line_regexp: bool,
     files: Vec<String>,
+    from_list: bool,
     ignore_case: bool,
     line_number: bool,
     invert: bool,
     count: bool,
     files_with_matches: bool,
-    recursive: bool,
+    files_without_match: bool,
+    max_count: Option<usize>,
+    quiet: bool,
+    // `-H`/`-h`; otherwise file names are shown when several files are
+    // searched.
+    with_filename: Option<bool>,
+    // `--label`: the name shown for stdin.
+    label: Option<String>,
+    walk: walk::Options,
+    binary_files: BinaryFiles,
+    // `--search-zip`: search the entries of zip archives and Office
+    // documents.
+    search_zip: bool,
+    before_context: usize,
+    after_context: usize,
+    group_separator: Option<String>,
+    only_matching: bool,
+    byte_offset: bool,
+    column: bool,
+    threads: usize,
+    sort_by_path: bool,
+    heading: bool,
+    vimgrep: bool,
+    null: bool,
+    replace: Option<String>,
+    in_place: bool,
+    multiline: bool,
+    stats: Option<stats::Format>,
+}
+
+impl Default for Opts {
+    fn default() -> Self {
+        Opts {
+            patterns: Vec::new(),
+            fixed_strings: false,
+            syntax: syntax::Syntax::Basic,
+            word_regexp: false,
+            line_regexp: false,
+            files: Vec::new(),
+            from_list: false,
+            ignore_case: false,
+            line_number: false,
+            invert: false,
+            count: false,
+            files_with_matches: false,
+            files_without_match: false,
+            max_count: None,
+            quiet: false,
+            with_filename: None,
+            label: None,
+            walk: walk::Options::default(),
+            binary_files: BinaryFiles::Binary,
+            search_zip: false,
+            before_context: 0,
+            after_context: 0,
+            group_separator: Some("--".to_string()),
+            only_matching: false,
+            byte_offset: false,
+            column: false,
+            threads: 0,
+            sort_by_path: true,
+            heading: false,
+            vimgrep: false,
+            null: false,
+            replace: None,
+            in_place: false,
+            multiline: false,
+            stats: None,
+        }
+    }
+}
+
+fn parse_count(parser: &mut lexopt::Parser) -> usize {
+    parser
+        .value()
+        .unwrap_or_else(|e| error::err(TOOL, &e.to_string()))
+        .parse()
+        .unwrap_or_else(|e| error::err(TOOL, &e.to_string()))
+}
+
+fn parse_string(parser: &mut lexopt::Parser) -> String {
+    parser
+        .value()
+        .unwrap_or_else(|e| error::err(TOOL, &e.to_string()))
+        .into_string()
+        .unwrap_or_else(|_| error::err(TOOL, "invalid UTF-8 in argument"))
+}
+
+/// Reads one pattern per line from `path` (`-` for stdin). An empty file
+/// contributes no patterns.
+fn read_pattern_file(path: &str) -> io::Result<Vec<String>> {
+    let mut text = String::new();
+    encoding::open_input_or_stdin((path != "-").then_some(path))?.read_to_string(&mut text)?;
+    Ok(text.lines().map(str::to_string).collect())
 }
 
 fn parse_args() -> Opts {
     let mut parser = lexopt::Parser::from_env();
-    let mut ignore_case = false;
-    let mut line_number = false;
-    let mut invert = false;
-    let mut count = false;
-    let mut files_with_matches = false;
-    let mut recursive = false;
+    let mut opts = Opts::default();
     let mut positionals: Vec<String> = Vec::new();
+    let mut listed: Vec<String> = Vec::new();
+    let mut explicit_patterns = false;
+    let mut types = types::Types::default();
+    let mut selected_types: Vec<(String, bool)> = Vec::new();
+    let mut type_list = false;
 
     while let Some(arg) = parser
         .next()
         .unwrap_or_else(|e| error::err(TOOL, &e.to_string()))
     {
         match arg {
-            Short('i') | Long("ignore-case") => ignore_case = true,
-            Short('n') | Long("line-number") => line_number = true,
-            Short('v') | Long("invert-match") => invert = true,
-            Short('c') | Long("count") => count = true,
-            Short('l') | Long("files-with-matches") => files_with_matches = true,
-            Short('r') | Long("recursive") => recursive = true,
+            Short('i') | Long("ignore-case") => opts.ignore_case = true,
+            Short('w') | Long("word-regexp") => opts.word_regexp = true,
+            Short('x') | Long("line-regexp") => opts.line_regexp = true,
+            Short('n') | Long("line-number") => opts.line_number = true,
+            Short('v') | Long("invert-match") => opts.invert = true,
+            Short('c') | Long("count") => opts.count = true,
+            Short('l') | Long("files-with-matches") => opts.files_with_matches = true,
+            Short('L') | Long("files-without-match") => opts.files_without_match = true,
+            Short('m') | Long("max-count") => opts.max_count = Some(parse_count(&mut parser)),
+            Short('q') | Long("quiet") | Long("silent") => opts.quiet = true,
+            Short('s') | Long("no-messages") => opts.walk.no_messages = true,
+            Short('H') | Long("with-filename") => opts.with_filename = Some(true),
+            Short('h') | Long("no-filename") => opts.with_filename = Some(false),
+            Long("label") => opts.label = Some(parse_string(&mut parser)),
+            Short('r') | Long("recursive") => opts.walk.recursive = true,
+            Short('R') | Long("dereference-recursive") => {
+                opts.walk.recursive = true;
+                opts.walk.follow_links = true;
+            }
+            Long("max-depth") => opts.walk.max_depth = Some(parse_count(&mut parser)),
+            Long("one-file-system") => opts.walk.one_file_system = true,
+            Short('A') | Long("after-context") => opts.after_context = parse_count(&mut parser),
+            Short('B') | Long("before-context") => opts.before_context = parse_count(&mut parser),
+            Short('C') | Long("context") => {
+                let n = parse_count(&mut parser);
+                opts.before_context = n;
+                opts.after_context = n;
+            }
+            Long("group-separator") => opts.group_separator = Some(parse_string(&mut parser)),
+            Long("no-group-separator") => opts.group_separator = None,
+            Short('F') | Long("fixed-strings") => opts.fixed_strings = true,
+            Short('G') | Long("basic-regexp") => {
+                opts.fixed_strings = false;
+                opts.syntax = syntax::Syntax::Basic;
+            }
+            Short('E') | Long("extended-regexp") => {
+                opts.fixed_strings = false;
+                opts.syntax = syntax::Syntax::Extended;
+            }
+            Short('e') | Long("regexp") => {
+                let p = parse_string(&mut parser);
+                opts.patterns.extend(matcher::split_patterns(&p));
+                explicit_patterns = true;
+            }
+            Short('f') | Long("file") => {
+                let path = parse_string(&mut parser);
+                let patterns = read_pattern_file(&path)
+                    .unwrap_or_else(|e| error::err(TOOL, &format!("{path}: {e}")));
+                opts.patterns.extend(patterns);
+                explicit_patterns = true;
+            }
+            Short('o') | Long("only-matching") => opts.only_matching = true,
+            Short('b') | Long("byte-offset") => opts.byte_offset = true,
+            Long("column") => opts.column = true,
+            Short('a') | Long("text") => opts.binary_files = BinaryFiles::Text,
+            Short('I') => opts.binary_files = BinaryFiles::WithoutMatch,
+            Long("search-zip") => opts.search_zip = true,
+            Long("binary-files") => {
+                opts.binary_files = BinaryFiles::parse(&parse_string(&mut parser))
+                    .unwrap_or_else(|e| error::err(TOOL, &e));
+            }
+            Long("hidden") => opts.walk.hidden = true,
+            Long("respect-gitignore") => opts.walk.gitignore = true,
+            Long("include") => opts.walk.filters.include(&parse_string(&mut parser)),
+            Long("exclude") => opts.walk.filters.exclude(&parse_string(&mut parser)),
+            Long("exclude-dir") => opts.walk.filters.exclude_dir(&parse_string(&mut parser)),
+            Long("exclude-from") => {
+                let path = parse_string(&mut parser);
+                let globs = read_pattern_file(&path)
+                    .unwrap_or_else(|e| error::err(TOOL, &format!("{path}: {e}")));
+                for glob in globs.iter().filter(|g| !g.is_empty()) {
+                    opts.walk.filters.exclude(glob);
+                }
+            }
+            Short('t') | Long("type") => selected_types.push((parse_string(&mut parser), true)),
+            Short('T') | Long("type-not") => {
+                selected_types.push((parse_string(&mut parser), false));
+            }
+            Long("type-add") => types
+                .add(&parse_string(&mut parser))
+                .unwrap_or_else(|e| error::err(TOOL, &e)),
+            Long("type-list") => type_list = true,
+            Short('U') | Long("multiline") => opts.multiline = true,
+            Long("replace") => opts.replace = Some(parse_string(&mut parser)),
+            Long("in-place") => opts.in_place = true,
+            Long("heading") => opts.heading = true,
+            Long("vimgrep") => opts.vimgrep = true,
+            Short('Z') | Long("null") => opts.null = true,
+            Long("stats") => {
+                opts.stats = Some(match parser.optional_value() {
+                    Some(v) => stats::Format::parse(&v.to_string_lossy())
+                        .unwrap_or_else(|e| error::err(TOOL, &e)),
+                    None => stats::Format::Text,
+                });
+            }
+            Short('j') | Long("threads") => opts.threads = parse_count(&mut parser),
+            Long("sort") => {
+                opts.sort_by_path = match parse_string(&mut parser).as_str() {
+                    "path" => true,
+                    "none" => false,
+                    other => error::err(
+                        TOOL,
+                        &format!(
+                            "invalid argument '{other}' for --sort (expected 'path' or 'none')"
+                        ),
+                    ),
+                }
+            }
+            Long("files-from") => {
+                listed.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Newline));
+                opts.from_list = true;
+            }
+            Long("files0-from") => {
+                listed.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Nul));
+                opts.from_list = true;
+            }
             Long("help") => {
                 cli::print_help(TOOL, USAGE);
                 std::process::exit(0);
@@ -56,163 +305,934 @@ fn parse_args() -> Opts {
         }
     }
 
-    if positionals.is_empty() {
-        error::err(TOOL, "missing PATTERN argument");
+    if type_list {
+        print!("{}", types.list());
+        std::process::exit(0);
+    }
+    // Resolved after parsing, so `--type-add` may follow the `-t` using it.
+    for (name, select) in &selected_types {
+        let globs = types.globs(name).unwrap_or_else(|e| error::err(TOOL, &e));
+        if *select {
+            opts.walk.filters.select_type(globs);
+        } else {
+            opts.walk.filters.negate_type(globs);
+        }
     }
 
-    let pattern = positionals.remove(0);
-    let files = positionals;
+    if !explicit_patterns {
+        if positionals.is_empty() {
+            error::err(TOOL, "missing PATTERN argument");
+        }
+        opts.patterns = matcher::split_patterns(&positionals.remove(0));
+    }
 
-    Opts {
-        pattern,
-        files,
-        ignore_case,
-        line_number,
-        invert,
-        count,
-        files_with_matches,
-        recursive,
+    opts.files = positionals;
+    opts.files.extend(listed);
+    if opts.in_place {
+        if opts.replace.is_none() {
+            error::err(TOOL, "--in-place requires --replace");
+        }
+        if opts.files.is_empty() && !opts.from_list {
+            error::err(TOOL, "--in-place requires file operands");
+        }
+        if opts.files.iter().any(|f| f == "-") {
+            error::err(TOOL, "--in-place cannot rewrite standard input");
+        }
+        if opts.multiline && opts.invert {
+            error::err(TOOL, "--in-place with -U cannot be combined with -v");
+        }
     }
+    if opts.vimgrep {
+        // One `path:line:column:text` record per match, without context.
+        opts.line_number = true;
+        opts.column = true;
+        opts.with_filename.get_or_insert(true);
+        opts.before_context = 0;
+        opts.after_context = 0;
+    }
+    opts
 }
 
 const USAGE: &str = "\
 Usage: grep [OPTIONS] PATTERN [FILE...]
+       grep [OPTIONS] -e PATTERN... [FILE...]
+       grep [OPTIONS] -f PATTERN_FILE [FILE...]
 
-Search for PATTERN in each FILE (or stdin).
+Search for PATTERN in each FILE (or stdin, also named by -).
 
 Options:
+  -G, --basic-regexp        Patterns are basic regexes (the default)
+  -E, --extended-regexp     Patterns are extended regexes
+  -F, --fixed-strings       Treat patterns as literal strings, not regexes
+  -e, --regexp=PATTERN      Use PATTERN; may be given more than once
+  -f, --file=FILE           Read patterns from FILE, one per line
   -i, --ignore-case        Case-insensitive matching
+  -w, --word-regexp         Match only whole words
+  -x, --line-regexp         Match only whole lines
   -n, --line-number         Prefix matches with line number
   -v, --invert-match        Select non-matching lines
   -c, --count               Print count of matching lines per file
   -l, --files-with-matches  Print only filenames with matches
-  -r, --recursive           Search directories recursively
+  -L, --files-without-match Print only filenames without matches
+  -m, --max-count=NUM       Stop reading a file after NUM selected lines
+  -q, --quiet, --silent     Print nothing; exit 0 at the first match
+  -s, --no-messages         Suppress messages about unreadable files
+  -H, --with-filename       Print the file name for each match
+  -h, --no-filename         Never print file names
+      --label=NAME          Show NAME as the file name for stdin
+  -r, --recursive           Search directories recursively, skipping links
+  -R, --dereference-recursive
+                            Search directories recursively, following links
+      --max-depth=NUM       Search at most NUM levels below each directory
+      --one-file-system     Don't descend into other file systems with -r
+  -a, --text                Search binary files as if they were text
+  -I                        Skip binary files (same as --binary-files=without-match)
+      --binary-files=TYPE   How to treat binary files: binary (default),
+                            text or without-match
+      --search-zip          Search the text entries of .zip, .jar, .nupkg,
+                            .docx, .xlsx and similar archives
+      --hidden              Search hidden files and directories with -r
+      --respect-gitignore   Skip files ignored by .gitignore and .ignore with -r
+      --include=GLOB        Search only files whose name matches GLOB
+      --exclude=GLOB        Skip files whose name matches GLOB
+      --exclude-dir=GLOB    Skip directories whose name matches GLOB
+      --exclude-from=FILE   Skip files matching any glob listed in FILE
+  -t, --type=TYPE           With -r, search only files of TYPE (see --type-list)
+  -T, --type-not=TYPE       With -r, skip files of TYPE
+      --type-add=NAME:GLOB  Add GLOB to file type NAME, defining it if new
+      --type-list           List the known file types and exit
+  -A, --after-context=NUM   Print NUM lines of trailing context
+  -B, --before-context=NUM  Print NUM lines of leading context
+  -C, --context=NUM         Print NUM lines of leading and trailing context
+      --group-separator=SEP Print SEP between context groups (default: --)
+      --no-group-separator  Do not print a separator between context groups
+  -o, --only-matching       Print only the matched parts of each line
+  -b, --byte-offset         Prefix output with the 0-based byte offset
+      --column              Prefix output with the 1-based column of the match
+  -U, --multiline           Search each file as a whole, so matches may span
+                            lines; (?s) lets . match a newline
+      --replace=TEMPLATE    Print lines with each match replaced by TEMPLATE;
+                            $1 or ${name} expand to capture groups
+      --in-place            With --replace, rewrite the files instead
+      --heading             Print each file name once, above its matches
+      --vimgrep             Print path:line:column:text for every match
+  -Z, --null                Print a NUL byte after each file name
+  -j, --threads=NUM         Search files with NUM threads (default: one per CPU)
+      --sort=ORDER          Output order with several threads: path (default)
+                            or none (as files finish)
+      --stats[=FORMAT]      Print search statistics to stderr when done, as
+                            text (the default) or json
+      --files-from=FILE     Read file names, one per line, from FILE
+      --files0-from=FILE    Read NUL-separated file names from FILE
       --help                Show this help
       --version             Show version";
 
-fn is_binary(path: &Path) -> bool {
-    let Ok(file) = fs::File::open(path) else {
-        return false;
-    };
-    let mut buf = [0u8; 8192];
-    let n = io::Read::read(&mut &file, &mut buf).unwrap_or(0);
-    buf[..n].contains(&0)
+struct Printer<'a, W: Write> {
+    out: &'a mut W,
+    filename: Option<&'a str>,
+    show_prefix: bool,
+    opts: &'a Opts,
+    // `--heading`: whether the file name has been printed yet.
+    heading_done: bool,
 }
 
-fn collect_files(paths: &[String], recursive: bool) -> Vec<String> {
-    let mut result = Vec::new();
-    for p in paths {
-        let path = Path::new(p);
-        if path.is_dir() {
-            if recursive {
-                walk_dir(path, &mut result);
-            } else {
-                error::warn(TOOL, &format!("{p}: Is a directory"));
+impl<W: Write> Printer<'_, W> {
+    /// Writes `name` followed by `end`, or by a NUL byte with `--null`.
+    fn file_name(&mut self, name: &str, end: char) -> io::Result<()> {
+        write!(self.out, "{name}")?;
+        if self.opts.null {
+            self.out.write_all(b"\0")
+        } else {
+            write!(self.out, "{end}")
+        }
+    }
+
+    /// With `--heading`, writes the file name above its first line of
+    /// output. Returns whether that output is grouped under the name.
+    fn heading(&mut self) -> io::Result<bool> {
+        let (true, true, Some(name)) = (self.opts.heading, self.show_prefix, self.filename) else {
+            return Ok(false);
+        };
+        if !self.heading_done {
+            self.heading_done = true;
+            self.file_name(name, '\n')?;
+            if self.opts.null {
+                writeln!(self.out)?;
             }
+        }
+        Ok(true)
+    }
+
+    fn prefix(
+        &mut self,
+        number: usize,
+        offset: usize,
+        column: Option<usize>,
+        sep: char,
+    ) -> io::Result<()> {
+        if self.heading()? {
+            write!(self.out, "  ")?;
+        } else if let (true, Some(name)) = (self.show_prefix, self.filename) {
+            self.file_name(name, sep)?;
+        }
+        if self.opts.line_number {
+            write!(self.out, "{number}{sep}")?;
+        }
+        if let (true, Some(col)) = (self.opts.column, column) {
+            write!(self.out, "{col}{sep}")?;
+        }
+        if self.opts.byte_offset {
+            write!(self.out, "{offset}{sep}")?;
+        }
+        Ok(())
+    }
+
+    /// `-q`, `-l` and `-L` are settled by the first selected line, so the
+    /// rest of the file need not be read.
+    fn settle(&mut self) -> io::Result<bool> {
+        if let (true, false, Some(name)) =
+            (self.opts.files_with_matches, self.opts.quiet, self.filename)
+        {
+            self.file_name(name, '\n')?;
+        }
+        Ok(!self.opts.files_without_match)
+    }
+
+    fn binary_matches(&mut self) -> io::Result<bool> {
+        if self.heading()? {
+            writeln!(self.out, "  Binary file matches")?;
         } else {
-            result.push(p.clone());
+            let name = self.filename.unwrap_or(STDIN_LABEL);
+            writeln!(self.out, "Binary file {name} matches")?;
         }
+        Ok(true)
     }
-    result
-}
 
-fn walk_dir(dir: &Path, out: &mut Vec<String>) {
-    let entries = match fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(e) => {
-            error::warn(TOOL, &format!("{}: {e}", dir.display()));
-            return;
+    /// Ends a file's output: the `-L` listing or the `-c` count.
+    fn summary(&mut self, found: bool, count: usize) -> io::Result<bool> {
+        if self.opts.files_without_match {
+            if let (false, Some(name)) = (self.opts.quiet, self.filename) {
+                self.file_name(name, '\n')?;
+            }
+            return Ok(true);
         }
-    };
-    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-    entries.sort_by_key(|e| e.file_name());
-    for entry in entries {
-        let name = entry.file_name();
-        let name_str = name.to_string_lossy();
-        if name_str.starts_with('.') {
-            continue;
+        if self.opts.count && !self.opts.quiet {
+            if let (true, Some(name)) = (self.show_prefix, self.filename) {
+                self.file_name(name, ':')?;
+            }
+            writeln!(self.out, "{count}")?;
+        }
+        Ok(found)
+    }
+
+    fn context(&mut self, number: usize, offset: usize, line: &str) -> io::Result<()> {
+        self.prefix(number, offset, None, '-')?;
+        writeln!(self.out, "{line}")
+    }
+
+    fn matched(
+        &mut self,
+        number: usize,
+        offset: usize,
+        line: &str,
+        matcher: &Matcher,
+    ) -> io::Result<()> {
+        // With `--replace`, the line as it would read after substitution.
+        let text = match &self.opts.replace {
+            Some(template) if !self.opts.only_matching => {
+                Cow::Owned(matcher.replace_all(line, template))
+            }
+            _ => Cow::Borrowed(line),
+        };
+        if self.opts.only_matching || self.opts.vimgrep {
+            let mut matches = matcher.find_iter(line).filter(|(s, e)| e > s).peekable();
+            if matches.peek().is_none() && self.opts.vimgrep {
+                // `-v`, or only empty matches: one record at column 1.
+                self.prefix(number, offset, Some(1), ':')?;
+                return writeln!(self.out, "{text}");
+            }
+            for (start, end) in matches {
+                if self.opts.only_matching {
+                    self.prefix(number, offset + start, Some(start + 1), ':')?;
+                    match &self.opts.replace {
+                        Some(t) => writeln!(self.out, "{}", matcher.expand(line, start, end, t))?,
+                        None => writeln!(self.out, "{}", &line[start..end])?,
+                    }
+                } else {
+                    self.prefix(number, offset, Some(start + 1), ':')?;
+                    writeln!(self.out, "{text}")?;
+                }
+            }
+            return Ok(());
         }
-        let path = entry.path();
-        if path.is_dir() {
-            walk_dir(&path, out);
-        } else if !is_binary(&path) {
-            out.push(path.to_string_lossy().into_owned());
+        let column = matcher.find_at(line, 0).map(|(start, _)| start + 1);
+        self.prefix(number, offset, column, ':')?;
+        writeln!(self.out, "{text}")
+    }
+}
+
+/// How many bytes of complete lines at the start of `chunk` can be skipped
+/// because none of them contains a prefilter match.
+fn skippable(prefilter: &Prefilter, chunk: &[u8]) -> usize {
+    let Some(last) = prefilter::memrchr(b'\n', chunk) else {
+        return 0;
+    };
+    let lines = &chunk[..=last];
+    match prefilter.find(lines) {
+        Some(at) => prefilter::memrchr(b'\n', &lines[..at]).map_or(0, |nl| nl + 1),
+        None => lines.len(),
+    }
+}
+
+/// Where the last `n` lines of `lines`, which ends with a newline, start.
+fn last_lines(lines: &[u8], n: usize) -> usize {
+    let mut start = lines.len();
+    for _ in 0..n {
+        if start == 0 {
+            break;
         }
+        start = prefilter::memrchr(b'\n', &lines[..start - 1]).map_or(0, |nl| nl + 1);
     }
+    start
+}
+
+fn trim_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
 }
 
 fn search_reader(
     reader: impl BufRead,
     filename: Option<&str>,
-    re: &Regex,
+    matcher: &Matcher,
     opts: &Opts,
     show_prefix: bool,
     out: &mut impl Write,
+) -> io::Result<bool> {
+    let mut counts = stats::FileCounts::default();
+    let result = if opts.multiline {
+        search_multiline(
+            reader,
+            filename,
+            matcher,
+            opts,
+            show_prefix,
+            out,
+            &mut counts,
+        )
+    } else {
+        search_lines(
+            reader,
+            filename,
+            matcher,
+            opts,
+            show_prefix,
+            out,
+            &mut counts,
+        )
+    };
+    stats::STATS.searched(&counts);
+    result
+}
+
+fn search_lines(
+    mut reader: impl BufRead,
+    filename: Option<&str>,
+    matcher: &Matcher,
+    opts: &Opts,
+    show_prefix: bool,
+    out: &mut impl Write,
+    counts: &mut stats::FileCounts,
 ) -> io::Result<bool> {
     let mut match_count: usize = 0;
     let mut found = false;
+    let print_lines =
+        !opts.count && !opts.files_with_matches && !opts.files_without_match && !opts.quiet;
+    let max_reached = |count: usize| opts.max_count.is_some_and(|max| count >= max);
+    let (before_context, after_context) = if opts.only_matching {
+        (0, 0)
+    } else {
+        (opts.before_context, opts.after_context)
+    };
+    let context = before_context > 0 || after_context > 0;
+    let mut before: VecDeque<(usize, usize, String)> = VecDeque::with_capacity(before_context);
+    let mut after_left = 0;
+    let mut last_printed: Option<usize> = None;
+    let mut printer = Printer {
+        out,
+        filename,
+        show_prefix,
+        opts,
+        heading_done: false,
+    };
+    let mut binary = false;
+    let mut buf = Vec::new();
+    let mut offset = 0;
+    let mut number = 0;
+    // With -v, the lines without a literal are the ones selected.
+    let prefilter = matcher.prefilter().filter(|_| !opts.invert);
 
-    for (i, line_result) in reader.lines().enumerate() {
-        let line = line_result?;
-        let matches = re.is_match(&line);
-        let selected = if opts.invert { !matches } else { matches };
+    loop {
+        // `-m`: stop reading once the last match's trailing context is out.
+        if max_reached(match_count) && after_left == 0 {
+            break;
+        }
+        // Skip the complete lines at the front of the read buffer that come
+        // before the first possible match, without decoding them. A NUL
+        // among them is left to the line-by-line search to deal with.
+        if let (Some(prefilter), 0) = (prefilter, after_left) {
+            let chunk = reader.fill_buf()?;
+            let skip = skippable(prefilter, chunk);
+            let skipped = &chunk[..skip];
+            if skip > 0
+                && (binary
+                    || opts.binary_files == BinaryFiles::Text
+                    || !encoding::is_binary(skipped))
+            {
+                if print_lines && before_context > 0 {
+                    let tail = last_lines(skipped, before_context);
+                    let mut n = number + prefilter::count(b'\n', &skipped[..tail]);
+                    let mut off = offset + tail;
+                    for line in skipped[tail..].split_inclusive(|&b| b == b'\n') {
+                        n += 1;
+                        if before.len() == before_context {
+                            before.pop_front();
+                        }
+                        let text = String::from_utf8_lossy(line);
+                        before.push_back((n, off, trim_line_ending(&text).to_string()));
+                        off += line.len();
+                    }
+                }
+                number += prefilter::count(b'\n', skipped);
+                offset += skip;
+                counts.bytes += skip as u64;
+                reader.consume(skip);
+                continue;
+            }
+        }
+        buf.clear();
+        let len = reader.read_until(b'\n', &mut buf)?;
+        if len == 0 {
+            break;
+        }
+        number += 1;
+        counts.bytes += len as u64;
+        // Like GNU grep, a file becomes binary once a NUL turns up; lines
+        // before it have already been searched as text.
+        if !binary && opts.binary_files != BinaryFiles::Text && encoding::is_binary(&buf) {
+            counts.binary = true;
+            if opts.binary_files == BinaryFiles::WithoutMatch {
+                // Still a file without a match for `-L`.
+                if opts.files_without_match {
+                    break;
+                }
+                return Ok(false);
+            }
+            binary = true;
+        }
+        let line_offset = offset;
+        offset += len;
+        // Bytes that are not UTF-8 (e.g. a legacy code page) are matched and
+        // printed as U+FFFD rather than failing the whole file.
+        let text = String::from_utf8_lossy(&buf);
+        let line = trim_line_ending(&text);
+        let matches = matcher.is_match(line);
+        let selected = matches != opts.invert && !max_reached(match_count);
 
         if selected {
             found = true;
             match_count += 1;
+            counts.lines += 1;
+            if opts.stats.is_some() && matches {
+                counts.matches += matcher.find_iter(line).filter(|(s, e)| e > s).count() as u64;
+            }
 
-            if opts.files_with_matches {
-                if let Some(name) = filename {
-                    writeln!(out, "{name}")?;
-                }
-                return Ok(true);
+            if opts.quiet || opts.files_with_matches || opts.files_without_match {
+                return printer.settle();
+            }
+            if print_lines && binary {
+                return printer.binary_matches();
             }
 
-            if !opts.count {
-                if show_prefix {
-                    if let Some(name) = filename {
-                        write!(out, "{name}:")?;
+            if print_lines {
+                let first = before.front().map_or(number, |(n, _, _)| *n);
+                if context && last_printed.is_some_and(|last| first > last + 1) {
+                    if let Some(sep) = &opts.group_separator {
+                        writeln!(printer.out, "{sep}")?;
                     }
                 }
-                if opts.line_number {
-                    write!(out, "{}:", i + 1)?;
+                for (n, off, ctx) in before.drain(..) {
+                    printer.context(n, off, &ctx)?;
+                }
+                if !(opts.only_matching && opts.invert) {
+                    printer.matched(number, line_offset, line, matcher)?;
                 }
-                writeln!(out, "{line}")?;
+                last_printed = Some(number);
+                after_left = after_context;
+            }
+        } else if print_lines && after_left > 0 {
+            printer.context(number, line_offset, line)?;
+            last_printed = Some(number);
+            after_left -= 1;
+        } else if print_lines && before_context > 0 {
+            if before.len() == before_context {
+                before.pop_front();
+            }
+            before.push_back((number, line_offset, line.to_string()));
+        }
+    }
+
+    printer.summary(found, match_count)
+}
+
+/// A run of lines (0-based, inclusive) touched by `-U` matches, with the
+/// byte ranges of those matches.
+struct MatchGroup {
+    first: usize,
+    last: usize,
+    matches: Vec<(usize, usize)>,
+}
+
+/// `-U`: searches the whole input at once, so a match can span lines. A
+/// match selects every line it touches, and matches that share a line are
+/// printed together. Context options do not apply.
+fn search_multiline(
+    mut reader: impl BufRead,
+    filename: Option<&str>,
+    matcher: &Matcher,
+    opts: &Opts,
+    show_prefix: bool,
+    out: &mut impl Write,
+    counts: &mut stats::FileCounts,
+) -> io::Result<bool> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    counts.bytes = bytes.len() as u64;
+    let mut printer = Printer {
+        out,
+        filename,
+        show_prefix,
+        opts,
+        heading_done: false,
+    };
+    let binary = opts.binary_files != BinaryFiles::Text && encoding::is_binary(&bytes);
+    counts.binary = binary;
+    if binary && opts.binary_files == BinaryFiles::WithoutMatch {
+        return if opts.files_without_match {
+            printer.summary(false, 0)
+        } else {
+            Ok(false)
+        };
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    // Each line with its offset, line ending included.
+    let lines: Vec<(usize, &str)> = text
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .collect();
+    let line_of = |pos: usize| lines.partition_point(|&(offset, _)| offset <= pos) - 1;
+    let max_reached = |count: usize| opts.max_count.is_some_and(|max| count >= max);
+
+    let mut groups: Vec<MatchGroup> = Vec::new();
+    for (start, end) in matcher.find_iter(&text) {
+        if start >= text.len() {
+            break;
+        }
+        let first = line_of(start);
+        let last = line_of(end.max(start + 1) - 1);
+        let count = groups.len();
+        match groups.last_mut() {
+            Some(group) if first <= group.last => {
+                group.last = group.last.max(last);
+                group.matches.push((start, end));
+            }
+            _ if !opts.invert && max_reached(count) => break,
+            _ => groups.push(MatchGroup {
+                first,
+                last,
+                matches: vec![(start, end)],
+            }),
+        }
+    }
+
+    let print_lines =
+        !opts.count && !opts.files_with_matches && !opts.files_without_match && !opts.quiet;
+    let settles = opts.quiet || opts.files_with_matches || opts.files_without_match;
+    let mut selected = 0;
+
+    if opts.invert {
+        let mut covered = vec![false; lines.len()];
+        for group in &groups {
+            covered[group.first..=group.last].fill(true);
+        }
+        for (i, &(offset, line)) in lines.iter().enumerate() {
+            if covered[i] || max_reached(selected) {
+                continue;
+            }
+            selected += 1;
+            counts.lines += 1;
+            if settles {
+                return printer.settle();
+            }
+            if print_lines && binary {
+                return printer.binary_matches();
+            }
+            if print_lines && !opts.only_matching {
+                printer.matched(i + 1, offset, trim_line_ending(line), matcher)?;
             }
         }
+        return printer.summary(selected > 0, selected);
     }
 
-    if opts.count {
-        if show_prefix {
-            if let Some(name) = filename {
-                write!(out, "{name}:")?;
+    for MatchGroup {
+        first,
+        last,
+        matches,
+    } in &groups
+    {
+        selected += last - first + 1;
+        counts.lines += (last - first + 1) as u64;
+        counts.matches += matches.len() as u64;
+        if settles {
+            return printer.settle();
+        }
+        if !print_lines {
+            continue;
+        }
+        if binary {
+            return printer.binary_matches();
+        }
+        if opts.only_matching || opts.vimgrep {
+            for &(start, end) in matches.iter().filter(|(s, e)| e > s) {
+                let (line_offset, line) = lines[line_of(start)];
+                let number = line_of(start) + 1;
+                let column = Some(start - line_offset + 1);
+                if opts.only_matching {
+                    printer.prefix(number, start, column, ':')?;
+                    let text = match &opts.replace {
+                        Some(t) => Cow::Owned(matcher.expand(&text, start, end, t)),
+                        None => Cow::Borrowed(&text[start..end]),
+                    };
+                    writeln!(printer.out, "{}", trim_line_ending(&text))?;
+                } else {
+                    printer.prefix(number, line_offset, column, ':')?;
+                    writeln!(printer.out, "{}", trim_line_ending(line))?;
+                }
             }
+            continue;
         }
-        writeln!(out, "{match_count}")?;
+        let region_start = lines[*first].0;
+        let region_end = lines[*last].0 + lines[*last].1.len();
+        let body = match &opts.replace {
+            Some(template) => {
+                let mut body = String::new();
+                let mut at = region_start;
+                for &(start, end) in matches {
+                    body.push_str(&text[at..start]);
+                    body.push_str(&matcher.expand(&text, start, end, template));
+                    at = end;
+                }
+                body.push_str(&text[at..region_end]);
+                Cow::Owned(body)
+            }
+            None => Cow::Borrowed(&text[region_start..region_end]),
+        };
+        let column = matches[0].0 - region_start + 1;
+        for (k, line) in body.split_inclusive('\n').enumerate() {
+            let offset = lines.get(first + k).map_or(region_start, |l| l.0);
+            printer.prefix(first + k + 1, offset, (k == 0).then_some(column), ':')?;
+            writeln!(printer.out, "{}", trim_line_ending(line))?;
+        }
+    }
+    printer.summary(!groups.is_empty(), selected)
+}
+
+const STDIN_LABEL: &str = "(standard input)";
+
+fn stdin_label(opts: &Opts) -> &str {
+    opts.label.as_deref().unwrap_or(STDIN_LABEL)
+}
+
+fn search_path(
+    path: &str,
+    matcher: &Matcher,
+    opts: &Opts,
+    show_prefix: bool,
+    out: &mut impl Write,
+) -> io::Result<bool> {
+    if path == "-" {
+        let reader = encoding::open_stdin()?;
+        return search_reader(
+            reader,
+            Some(stdin_label(opts)),
+            matcher,
+            opts,
+            show_prefix,
+            out,
+        );
+    }
+    if opts.in_place {
+        let mut counts = stats::FileCounts::default();
+        let result = replace::rewrite_file(path, matcher, opts, &mut counts);
+        stats::STATS.searched(&counts);
+        return result;
     }
+    if opts.search_zip && zip::is_archive(path) {
+        return search_archive(path, matcher, opts, out);
+    }
+    let reader = encoding::open_input(path)?;
+    search_reader(reader, Some(path), matcher, opts, show_prefix, out)
+}
 
+/// `--search-zip`: searches each entry of the archive at `path` as a file
+/// named `path!entry`. The XML parts of Office documents are searched as
+/// the text they hold.
+fn search_archive(
+    path: &str,
+    matcher: &Matcher,
+    opts: &Opts,
+    out: &mut impl Write,
+) -> io::Result<bool> {
+    let archive = std::fs::read(path)?;
+    let entries =
+        zip::entries(&archive).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let office = zip::is_office(path);
+    // An archive holds many files, so name them unless `-h`.
+    let show_prefix = opts.with_filename.unwrap_or(true);
+    let mut found = false;
+    for entry in &entries {
+        if entry.is_dir() || office && zip::is_office_metadata(&entry.name) {
+            continue;
+        }
+        let name = format!("{path}!{}", entry.name);
+        let mut contents = match zip::read(&archive, entry) {
+            Ok(contents) => contents,
+            Err(e) => {
+                stats::STATS.unreadable();
+                opts.walk.warn(&format!("{name}: {e}"));
+                continue;
+            }
+        };
+        if office && entry.name.ends_with(".xml") {
+            contents = zip::office_text(&String::from_utf8_lossy(&contents)).into_bytes();
+        }
+        let reader = encoding::open_reader(&contents[..]);
+        found |= search_reader(reader, Some(&name), matcher, opts, show_prefix, out)?;
+        if found && opts.quiet {
+            break;
+        }
+    }
     Ok(found)
 }
 
+/// Puts a blank line between the output of successive files, for
+/// `--heading`.
+struct FileGroups<W: Write> {
+    out: W,
+    separate: bool,
+    // Whether any file has printed, and whether the current one has.
+    printed: bool,
+    started: bool,
+}
+
+impl<W: Write> FileGroups<W> {
+    fn new(out: W, separate: bool) -> Self {
+        FileGroups {
+            out,
+            separate,
+            printed: false,
+            started: false,
+        }
+    }
+
+    fn next_file(&mut self) {
+        self.started = false;
+    }
+}
+
+impl<W: Write> Write for FileGroups<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() && !self.started {
+            if self.separate && self.printed {
+                self.out.write_all(b"\n")?;
+            }
+            self.started = true;
+            self.printed = true;
+        }
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Searches the files one by one, writing straight to `out`. Errors other
+/// than a closed output are reported per file.
+fn search_sequential(
+    matcher: &Matcher,
+    opts: &Opts,
+    show_prefix: bool,
+    out: &mut FileGroups<impl Write>,
+) -> io::Result<bool> {
+    let mut any_match = false;
+    let mut result = Ok(());
+    walk::for_each_file(&opts.files, &opts.walk, &mut |path| {
+        out.next_file();
+        match search_path(&path, matcher, opts, show_prefix, out) {
+            Ok(found) => any_match |= found,
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                result = Err(e);
+                return false;
+            }
+            Err(e) => {
+                stats::STATS.unreadable();
+                opts.walk.warn(&format!("{path}: {e}"));
+            }
+        }
+        // `-q` is settled by the first match in any file.
+        !(opts.quiet && any_match)
+    });
+    result.map(|()| any_match)
+}
+
+/// One file's output, buffered by a worker thread until the main thread
+/// can print it.
+struct FileResult {
+    output: Vec<u8>,
+    found: bool,
+    warning: Option<String>,
+}
+
+/// Walks on one thread and searches on `threads` others. With `--sort=path`
+/// (the default) results are printed in walk order, so the output matches
+/// a sequential search; with `--sort=none`, as soon as each file is done.
+fn search_parallel(
+    matcher: &Matcher,
+    opts: &Opts,
+    show_prefix: bool,
+    threads: usize,
+    out: &mut FileGroups<impl Write>,
+) -> io::Result<bool> {
+    let stop = AtomicBool::new(false);
+    let (path_tx, path_rx) = mpsc::sync_channel::<(usize, String)>(threads * 4);
+    let path_rx = Mutex::new(path_rx);
+    let (result_tx, result_rx) = mpsc::channel::<(usize, FileResult)>();
+
+    thread::scope(|s| {
+        let stop = &stop;
+        s.spawn(move || {
+            let mut seq = 0;
+            walk::for_each_file(&opts.files, &opts.walk, &mut |path| {
+                let sent = !stop.load(Ordering::Relaxed) && path_tx.send((seq, path)).is_ok();
+                seq += 1;
+                sent
+            });
+        });
+        for _ in 0..threads {
+            let result_tx = result_tx.clone();
+            let path_rx = &path_rx;
+            s.spawn(move || loop {
+                let Ok((seq, path)) = path_rx.lock().unwrap().recv() else {
+                    break;
+                };
+                // Keep draining after a stop so the walker never blocks.
+                if stop.load(Ordering::Relaxed) {
+                    continue;
+                }
+                let mut output = Vec::new();
+                let result = match search_path(&path, matcher, opts, show_prefix, &mut output) {
+                    Ok(found) => FileResult {
+                        output,
+                        found,
+                        warning: None,
+                    },
+                    Err(e) => {
+                        stats::STATS.unreadable();
+                        FileResult {
+                            output,
+                            found: false,
+                            warning: Some(format!("{path}: {e}")),
+                        }
+                    }
+                };
+                if result_tx.send((seq, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_tx);
+
+        let mut any_match = false;
+        let mut emit = |r: FileResult| -> io::Result<()> {
+            out.next_file();
+            out.write_all(&r.output)?;
+            if let Some(w) = r.warning {
+                out.flush()?;
+                opts.walk.warn(&w);
+            }
+            any_match |= r.found;
+            Ok(())
+        };
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        let mut result = Ok(());
+        for (seq, r) in result_rx {
+            result = if opts.sort_by_path {
+                pending.insert(seq, r);
+                let mut res = Ok(());
+                while let Some(r) = pending.remove(&next) {
+                    next += 1;
+                    res = emit(r);
+                    if res.is_err() {
+                        break;
+                    }
+                }
+                res
+            } else {
+                emit(r)
+            };
+            if result.is_err() {
+                stop.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+        result.map(|()| any_match)
+    })
+}
+
 fn run() -> i32 {
+    let start = Instant::now();
     let opts = parse_args();
+    if opts.stats.is_some() {
+        stats::STATS.enable();
+    }
 
-    let re = match RegexBuilder::new(&opts.pattern)
-        .case_insensitive(opts.ignore_case)
-        .build()
-    {
-        Ok(r) => r,
+    let syntax = matcher::Options {
+        fixed: opts.fixed_strings,
+        syntax: opts.syntax,
+        ignore_case: opts.ignore_case,
+        word: opts.word_regexp,
+        line: opts.line_regexp,
+        captures: opts.replace.is_some(),
+        multiline: opts.multiline,
+    };
+    let matcher = match Matcher::new(&opts.patterns, syntax) {
+        Ok(m) => m,
         Err(e) => {
-            eprintln!("{TOOL}: invalid regex '{}': {e}", opts.pattern);
+            eprintln!("{TOOL}: {e}");
             return 2;
         }
     };
 
     let stdout = io::stdout();
     let mut out = stdout.lock();
-    let mut any_match = false;
 
-    if opts.files.is_empty() {
+    let result = if opts.files.is_empty() && !opts.from_list {
         let reader = match encoding::open_stdin() {
             Ok(r) => r,
             Err(e) => {
@@ -220,44 +1240,49 @@ fn run() -> i32 {
                 return 2;
             }
         };
-        match search_reader(reader, None, &re, &opts, false, &mut out) {
-            Ok(found) => any_match = found,
-            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return 0,
-            Err(e) => {
-                eprintln!("{TOOL}: {e}");
-                return 2;
-            }
-        }
+        // Named only with -H, like a single file.
+        let show_prefix = opts.with_filename.unwrap_or(false);
+        search_reader(
+            reader,
+            Some(stdin_label(&opts)),
+            &matcher,
+            &opts,
+            show_prefix,
+            &mut out,
+        )
     } else {
-        let files = collect_files(&opts.files, opts.recursive);
-        let show_prefix = files.len() > 1;
-
-        for path in &files {
-            let reader = match encoding::open_input(path) {
-                Ok(r) => r,
-                Err(e) => {
-                    error::warn(TOOL, &format!("{path}: {e}"));
-                    continue;
-                }
-            };
-            match search_reader(reader, Some(path), &re, &opts, show_prefix, &mut out) {
-                Ok(found) => {
-                    if found {
-                        any_match = true;
-                    }
-                }
-                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return 0,
-                Err(e) => {
-                    error::warn(TOOL, &format!("{path}: {e}"));
-                }
-            }
+        // Like GNU grep: name files when there is more than one operand or
+        // a directory is searched recursively, unless -H or -h says otherwise.
+        let many = opts.files.len() > 1
+            || (opts.walk.recursive && opts.files.iter().any(|f| Path::new(f).is_dir()));
+        let show_prefix = opts.with_filename.unwrap_or(many);
+        let lists_lines =
+            !opts.count && !opts.files_with_matches && !opts.files_without_match && !opts.quiet;
+        let mut out = FileGroups::new(out, opts.heading && show_prefix && lists_lines);
+        let threads = match opts.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        if threads > 1 && many && !opts.quiet {
+            search_parallel(&matcher, &opts, show_prefix, threads, &mut out)
+        } else {
+            search_sequential(&matcher, &opts, show_prefix, &mut out)
         }
+    };
+
+    if let Some(format) = opts.stats {
+        let _ = io::stdout().flush();
+        eprint!("{}", stats::STATS.report(start.elapsed(), format));
     }
 
-    if any_match {
-        0
-    } else {
-        1
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(e) => {
+            eprintln!("{TOOL}: {e}");
+            2
+        }
     }
 }
 
@@ -268,6 +1293,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use matcher::{Regex, RegexBuilder};
     use std::io::{BufReader, Cursor};
 
     fn make_reader(s: &str) -> impl BufRead {
@@ -275,21 +1301,12 @@ mod tests {
     }
 
     fn default_opts() -> Opts {
-        Opts {
-            pattern: String::new(),
-            files: vec![],
-            ignore_case: false,
-            line_number: false,
-            invert: false,
-            count: false,
-            files_with_matches: false,
-            recursive: false,
-        }
+        Opts::default()
     }
 
     #[test]
     fn basic_match() {
-        let re = Regex::new("hello").unwrap();
+        let re = Matcher::from(Regex::new("hello").unwrap());
         let opts = default_opts();
         let mut out = Vec::new();
         let found = search_reader(
@@ -307,7 +1324,7 @@ mod tests {
 
     #[test]
     fn no_match() {
-        let re = Regex::new("xyz").unwrap();
+        let re = Matcher::from(Regex::new("xyz").unwrap());
         let opts = default_opts();
         let mut out = Vec::new();
         let found = search_reader(
@@ -325,10 +1342,12 @@ mod tests {
 
     #[test]
     fn case_insensitive() {
-        let re = RegexBuilder::new("hello")
-            .case_insensitive(true)
-            .build()
-            .unwrap();
+        let re = Matcher::from(
+            RegexBuilder::new("hello")
+                .case_insensitive(true)
+                .build()
+                .unwrap(),
+        );
         let opts = default_opts();
         let mut out = Vec::new();
         let found = search_reader(
@@ -346,7 +1365,7 @@ mod tests {
 
     #[test]
     fn line_numbers() {
-        let re = Regex::new("o").unwrap();
+        let re = Matcher::from(Regex::new("o").unwrap());
         let mut opts = default_opts();
         opts.line_number = true;
         let mut out = Vec::new();
@@ -364,7 +1383,7 @@ mod tests {
 
     #[test]
     fn invert_match() {
-        let re = Regex::new("foo").unwrap();
+        let re = Matcher::from(Regex::new("foo").unwrap());
         let mut opts = default_opts();
         opts.invert = true;
         let mut out = Vec::new();
@@ -382,7 +1401,7 @@ mod tests {
 
     #[test]
     fn count_mode() {
-        let re = Regex::new("a").unwrap();
+        let re = Matcher::from(Regex::new("a").unwrap());
         let mut opts = default_opts();
         opts.count = true;
         let mut out = Vec::new();
@@ -400,7 +1419,7 @@ mod tests {
 
     #[test]
     fn files_with_matches_mode() {
-        let re = Regex::new("hello").unwrap();
+        let re = Matcher::from(Regex::new("hello").unwrap());
         let mut opts = default_opts();
         opts.files_with_matches = true;
         let mut out = Vec::new();
@@ -419,7 +1438,7 @@ mod tests {
 
     #[test]
     fn multi_file_prefix() {
-        let re = Regex::new("x").unwrap();
+        let re = Matcher::from(Regex::new("x").unwrap());
         let opts = default_opts();
         let mut out = Vec::new();
         search_reader(
@@ -436,7 +1455,7 @@ mod tests {
 
     #[test]
     fn count_with_prefix() {
-        let re = Regex::new("a").unwrap();
+        let re = Matcher::from(Regex::new("a").unwrap());
         let mut opts = default_opts();
         opts.count = true;
         let mut out = Vec::new();
@@ -452,9 +1471,420 @@ mod tests {
         assert_eq!(String::from_utf8(out).unwrap(), "f.txt:2\n");
     }
 
+    fn search_str(re: &str, input: &str, opts: &Opts) -> String {
+        let re = Matcher::from(Regex::new(re).unwrap());
+        let mut out = Vec::new();
+        search_reader(make_reader(input), None, &re, opts, false, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn max_count_stops_after_trailing_context() {
+        let mut opts = default_opts();
+        opts.max_count = Some(2);
+        assert_eq!(search_str("x", "x1\na\nx2\nx3\n", &opts), "x1\nx2\n");
+        opts.after_context = 1;
+        opts.line_number = true;
+        // A match inside the trailing context is printed as context.
+        assert_eq!(
+            search_str("x", "x1\na\nx2\nx3\nx4\n", &opts),
+            "1:x1\n2-a\n3:x2\n4-x3\n"
+        );
+        opts.count = true;
+        assert_eq!(search_str("x", "x\nx\nx\n", &opts), "2\n");
+        opts.max_count = Some(0);
+        assert_eq!(search_str("x", "x\n", &opts), "0\n");
+    }
+
+    #[test]
+    fn files_without_match_and_quiet() {
+        let re = Matcher::from(Regex::new("x").unwrap());
+        let mut opts = default_opts();
+        opts.files_without_match = true;
+        for (input, listed) in [("a\nb\n", true), ("a\nx\n", false)] {
+            let mut out = Vec::new();
+            let found =
+                search_reader(make_reader(input), Some("f"), &re, &opts, false, &mut out).unwrap();
+            assert_eq!(found, listed);
+            assert_eq!(out, if listed { &b"f\n"[..] } else { b"" });
+        }
+
+        let mut opts = default_opts();
+        opts.quiet = true;
+        opts.count = true;
+        let mut out = Vec::new();
+        assert!(
+            search_reader(make_reader("x\nx\n"), Some("f"), &re, &opts, true, &mut out).unwrap()
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn vimgrep_prints_each_match() {
+        let re = Matcher::from(Regex::new("o").unwrap());
+        let mut opts = default_opts();
+        opts.vimgrep = true;
+        opts.line_number = true;
+        opts.column = true;
+        let mut out = Vec::new();
+        search_reader(
+            make_reader("foo\nbar\n"),
+            Some("f"),
+            &re,
+            &opts,
+            true,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "f:1:2:foo\nf:1:3:foo\n");
+
+        opts.invert = true;
+        let mut out = Vec::new();
+        search_reader(
+            make_reader("foo\nbar\n"),
+            Some("f"),
+            &re,
+            &opts,
+            true,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "f:2:1:bar\n");
+    }
+
+    #[test]
+    fn heading_groups_lines_under_file_name() {
+        let re = Matcher::from(Regex::new("x").unwrap());
+        let mut opts = default_opts();
+        opts.heading = true;
+        opts.line_number = true;
+        opts.after_context = 1;
+        let mut groups = FileGroups::new(Vec::new(), true);
+        for (name, input) in [("a", "x\ny\n"), ("b", "y\n"), ("c", "x\n")] {
+            groups.next_file();
+            search_reader(
+                make_reader(input),
+                Some(name),
+                &re,
+                &opts,
+                true,
+                &mut groups,
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(groups.out).unwrap(),
+            "a\n  1:x\n  2-y\n\nc\n  1:x\n"
+        );
+    }
+
+    #[test]
+    fn heading_groups_binary_matches() {
+        let re = Matcher::from(Regex::new("x").unwrap());
+        let mut opts = default_opts();
+        opts.heading = true;
+        let mut groups = FileGroups::new(Vec::new(), true);
+        for (name, input) in [("a", "x\n"), ("b", "x\0y\n"), ("c", "x\n")] {
+            groups.next_file();
+            search_reader(
+                make_reader(input),
+                Some(name),
+                &re,
+                &opts,
+                true,
+                &mut groups,
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(groups.out).unwrap(),
+            "a\n  x\n\nb\n  Binary file matches\n\nc\n  x\n"
+        );
+    }
+
+    fn search_multiline_str(pattern: &str, input: &str, opts: &mut Opts) -> String {
+        opts.multiline = true;
+        let m = Matcher::new(
+            &[pattern.to_string()],
+            matcher::Options {
+                multiline: true,
+                captures: opts.replace.is_some(),
+                ..matcher::Options::default()
+            },
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        search_reader(make_reader(input), None, &m, opts, false, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn multiline_prints_spanned_lines() {
+        let input = "a\nstart\nmiddle\nend\nb\n";
+        let mut opts = default_opts();
+        opts.line_number = true;
+        assert_eq!(
+            search_multiline_str(r"start\nmiddle\ne", input, &mut opts),
+            "2:start\n3:middle\n4:end\n"
+        );
+        assert_eq!(
+            search_multiline_str("(?s)art.*dle", input, &mut opts),
+            "2:start\n3:middle\n"
+        );
+        // `.` stops at a newline without `(?s)`.
+        assert_eq!(search_multiline_str("art.*dle", input, &mut opts), "");
+
+        opts.count = true;
+        assert_eq!(search_multiline_str(r"t\nm", input, &mut opts), "2\n");
+        opts.invert = true;
+        assert_eq!(search_multiline_str(r"t\nm", input, &mut opts), "3\n");
+    }
+
+    #[test]
+    fn multiline_merges_matches_sharing_a_line() {
+        let input = "ab\ncd\nef\ngh\n";
+        let mut opts = default_opts();
+        opts.line_number = true;
+        assert_eq!(
+            search_multiline_str(r"b\nc\|d\ne", input, &mut opts),
+            "1:ab\n2:cd\n3:ef\n"
+        );
+        opts.max_count = Some(1);
+        assert_eq!(
+            search_multiline_str(r"b\nc\|f\ng", input, &mut opts),
+            "1:ab\n2:cd\n"
+        );
+    }
+
+    #[test]
+    fn multiline_only_matching_and_replace() {
+        let input = "x\r\nkey:\r\n  value\r\n";
+        let mut opts = default_opts();
+        opts.only_matching = true;
+        opts.byte_offset = true;
+        assert_eq!(
+            search_multiline_str(r"key:\r\n *v", input, &mut opts),
+            "3:key:\r\n  v\n"
+        );
+        // `$` matches before a CRLF line ending.
+        assert_eq!(search_multiline_str("y:$", input, &mut opts), "5:y:\n");
+
+        let mut opts = default_opts();
+        opts.replace = Some("[$0]".to_string());
+        assert_eq!(
+            search_multiline_str(r"x\r\nk", input, &mut opts),
+            "[x\nk]ey:\n"
+        );
+    }
+
+    #[test]
+    fn after_context() {
+        let mut opts = default_opts();
+        opts.after_context = 1;
+        opts.line_number = true;
+        assert_eq!(
+            search_str("x", "x\na\nb\nx\nc\n", &opts),
+            "1:x\n2-a\n--\n4:x\n5-c\n"
+        );
+    }
+
+    #[test]
+    fn before_context_ring_is_bounded() {
+        let mut opts = default_opts();
+        opts.before_context = 2;
+        assert_eq!(search_str("x", "a\nb\nc\nd\nx\n", &opts), "c\nd\nx\n");
+    }
+
+    #[test]
+    fn overlapping_context_has_no_separator() {
+        let mut opts = default_opts();
+        opts.before_context = 1;
+        opts.after_context = 1;
+        assert_eq!(
+            search_str("x", "x\na\nx\nb\nc\nd\nx\n", &opts),
+            "x\na\nx\nb\n--\nd\nx\n"
+        );
+    }
+
+    #[test]
+    fn adjacent_groups_have_no_separator() {
+        let mut opts = default_opts();
+        opts.after_context = 1;
+        assert_eq!(search_str("x", "x\na\nx\n", &opts), "x\na\nx\n");
+    }
+
+    #[test]
+    fn custom_and_disabled_group_separator() {
+        let mut opts = default_opts();
+        opts.after_context = 1;
+        opts.group_separator = Some("==".to_string());
+        assert_eq!(search_str("x", "x\na\nb\nx\n", &opts), "x\na\n==\nx\n");
+        opts.group_separator = None;
+        assert_eq!(search_str("x", "x\na\nb\nx\n", &opts), "x\na\nx\n");
+    }
+
+    #[test]
+    fn context_with_invert() {
+        let mut opts = default_opts();
+        opts.invert = true;
+        opts.before_context = 1;
+        opts.line_number = true;
+        assert_eq!(search_str("x", "x\na\nx\nx\n", &opts), "1-x\n2:a\n");
+    }
+
+    #[test]
+    fn only_matching_prints_each_match() {
+        let mut opts = default_opts();
+        opts.only_matching = true;
+        opts.line_number = true;
+        assert_eq!(
+            search_str("[0-9]+", "id 12 and 345\nnone\n7\n", &opts),
+            "1:12\n1:345\n3:7\n"
+        );
+    }
+
+    #[test]
+    fn only_matching_skips_empty_matches() {
+        let mut opts = default_opts();
+        opts.only_matching = true;
+        assert_eq!(search_str("x*", "axxb\n", &opts), "xx\n");
+    }
+
+    #[test]
+    fn byte_offset_and_column() {
+        let mut opts = default_opts();
+        opts.byte_offset = true;
+        opts.column = true;
+        assert_eq!(search_str("b", "abc\r\nxxb\n", &opts), "2:0:abc\n3:5:xxb\n");
+        opts.only_matching = true;
+        assert_eq!(search_str("b", "abc\r\nxxb\n", &opts), "2:1:b\n3:7:b\n");
+    }
+
+    #[test]
+    fn only_matching_with_invert_prints_nothing() {
+        let mut opts = default_opts();
+        opts.only_matching = true;
+        opts.invert = true;
+        assert_eq!(search_str("x", "a\nx\n", &opts), "");
+    }
+
+    #[test]
+    fn fixed_strings_only_matching_prefers_longest() {
+        let syntax = matcher::Options {
+            fixed: true,
+            ..matcher::Options::default()
+        };
+        let matcher = Matcher::new(&["ab".to_string(), "abcd".to_string()], syntax).unwrap();
+        let mut opts = default_opts();
+        opts.only_matching = true;
+        let mut out = Vec::new();
+        search_reader(
+            make_reader("abcd ab\n"),
+            None,
+            &matcher,
+            &opts,
+            false,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "abcd\nab\n");
+    }
+
+    /// Like `search_str`, through `Matcher::new` so the prefilter is used.
+    fn search_prefiltered(pattern: &str, input: &str, opts: &Opts) -> String {
+        let m = Matcher::new(&[pattern.to_string()], matcher::Options::default()).unwrap();
+        assert!(m.prefilter().is_some());
+        let mut out = Vec::new();
+        search_reader(make_reader(input), None, &m, opts, false, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn prefilter_keeps_numbers_offsets_and_context() {
+        let input = "a\nb\nc\nkey=1\nd\nkeyless\nkey=2\n";
+        let mut opts = default_opts();
+        opts.line_number = true;
+        opts.byte_offset = true;
+        assert_eq!(
+            search_prefiltered("key=[0-9]", input, &opts),
+            "4:6:key=1\n7:22:key=2\n"
+        );
+
+        opts.byte_offset = false;
+        opts.before_context = 2;
+        opts.after_context = 1;
+        assert_eq!(
+            search_prefiltered("key=[0-9]", input, &opts),
+            "2-b\n3-c\n4:key=1\n5-d\n6-keyless\n7:key=2\n"
+        );
+
+        opts.invert = true;
+        opts.before_context = 0;
+        opts.after_context = 0;
+        assert_eq!(
+            search_prefiltered("key", input, &opts),
+            "1:a\n2:b\n3:c\n5:d\n"
+        );
+    }
+
+    #[test]
+    fn prefilter_notices_binary_lines_it_skips() {
+        let input = "a\nx\0y\nb\nkey\n";
+        let opts = default_opts();
+        assert_eq!(
+            search_prefiltered("key", input, &opts),
+            "Binary file (standard input) matches\n"
+        );
+    }
+
+    #[test]
+    fn binary_match_is_reported_once() {
+        let opts = default_opts();
+        assert_eq!(
+            search_str("ab", "ab 1\nx\0y\nab 2\nab 3\n", &opts),
+            "ab 1\nBinary file (standard input) matches\n"
+        );
+    }
+
+    #[test]
+    fn binary_files_modes() {
+        let input = "a\0b\nab\n";
+        let mut opts = default_opts();
+        opts.binary_files = BinaryFiles::Text;
+        assert_eq!(search_str("ab", input, &opts), "ab\n");
+
+        opts.binary_files = BinaryFiles::WithoutMatch;
+        let re = Matcher::from(Regex::new("ab").unwrap());
+        let mut out = Vec::new();
+        let found = search_reader(make_reader(input), None, &re, &opts, false, &mut out).unwrap();
+        assert!(!found);
+        assert!(out.is_empty());
+
+        opts.binary_files = BinaryFiles::Binary;
+        opts.count = true;
+        assert_eq!(search_str("b", input, &opts), "2\n");
+    }
+
+    #[test]
+    fn invalid_utf8_is_matched_lossily() {
+        let re = Matcher::from(Regex::new("caf").unwrap());
+        let mut out = Vec::new();
+        let found = search_reader(
+            &b"caf\xe9\nother\n"[..],
+            None,
+            &re,
+            &default_opts(),
+            false,
+            &mut out,
+        )
+        .unwrap();
+        assert!(found);
+        assert_eq!(String::from_utf8(out).unwrap(), "caf\u{FFFD}\n");
+    }
+
     #[test]
     fn invalid_regex() {
-        let result = RegexBuilder::new("[invalid").build();
-        assert!(result.is_err());
+        let patterns = ["[invalid".to_string()];
+        assert!(Matcher::new(&patterns, matcher::Options::default()).is_err());
     }
 }
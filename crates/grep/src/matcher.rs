@@ -0,0 +1,480 @@
+use crate::aho_corasick::AhoCorasick;
+use crate::prefilter::Prefilter;
+use crate::syntax::{self, Syntax};
+
+// The regex engine: `regex` with the `unicode` feature, `regex-lite` (ASCII
+// case folding and classes) otherwise. Their APIs match for what grep uses.
+#[cfg(feature = "unicode")]
+pub use regex::{escape, Regex, RegexBuilder};
+#[cfg(not(feature = "unicode"))]
+pub use regex_lite::{escape, Regex, RegexBuilder};
+
+/// The compiled form of grep's patterns.
+pub struct Matcher {
+    engine: Engine,
+    prefilter: Option<Prefilter>,
+}
+
+enum Engine {
+    Regex(Regex),
+    Literals(AhoCorasick),
+    Words(WordMatcher),
+}
+
+/// How patterns are interpreted.
+#[derive(Clone, Copy, Default)]
+pub struct Options {
+    pub fixed: bool,
+    pub syntax: Syntax,
+    pub ignore_case: bool,
+    pub word: bool,
+    pub line: bool,
+    // `--replace` needs capture groups, which the literal matcher lacks.
+    pub captures: bool,
+    // `-U`: haystacks hold many lines, and `^` and `$` match at each.
+    pub multiline: bool,
+}
+
+impl From<Regex> for Matcher {
+    fn from(re: Regex) -> Self {
+        Matcher {
+            engine: Engine::Regex(re),
+            prefilter: None,
+        }
+    }
+}
+
+impl Matcher {
+    /// Compiles `patterns`; a line matches if any of them matches.
+    pub fn new(patterns: &[String], opts: Options) -> Result<Self, String> {
+        let engine = Engine::new(patterns, opts)?;
+        // Unicode case folding matches "K" with the Kelvin sign, which an
+        // ASCII-folding scan would miss.
+        let prefilter = if opts.ignore_case && cfg!(feature = "unicode") {
+            None
+        } else if opts.fixed {
+            Prefilter::new(patterns, opts.ignore_case)
+        } else {
+            patterns
+                .iter()
+                .map(|p| syntax::required_literal(p, opts.syntax))
+                .collect::<Option<Vec<_>>>()
+                .and_then(|literals| Prefilter::new(&literals, opts.ignore_case))
+        };
+        Ok(Matcher { engine, prefilter })
+    }
+
+    /// Finds candidate lines: a line without a prefilter match cannot
+    /// match. None if the patterns have no required literal.
+    pub fn prefilter(&self) -> Option<&Prefilter> {
+        self.prefilter.as_ref()
+    }
+
+    pub fn find_at(&self, haystack: &str, at: usize) -> Option<(usize, usize)> {
+        match &self.engine {
+            Engine::Regex(re) => re.find_at(haystack, at).map(|m| (m.start(), m.end())),
+            Engine::Literals(ac) => ac.find_at(haystack, at),
+            Engine::Words(w) => w.find_at(haystack, at),
+        }
+    }
+
+    pub fn is_match(&self, haystack: &str) -> bool {
+        self.find_at(haystack, 0).is_some()
+    }
+
+    /// Expands `template` for the match at `start..end`: `$1` or `${1}`
+    /// is a capture group, `${name}` a named one, `$0` the whole match and
+    /// `$$` a dollar sign.
+    pub fn expand(&self, haystack: &str, start: usize, end: usize, template: &str) -> String {
+        let mut dst = String::new();
+        let caps = match &self.engine {
+            Engine::Regex(re) => re.captures_at(haystack, start),
+            // The match may have been shortened to end on a word boundary.
+            Engine::Words(w) => w.exact.captures(&haystack[start..end]),
+            // Only built without `Options::captures`.
+            Engine::Literals(_) => None,
+        };
+        match caps {
+            Some(caps) => caps.expand(template, &mut dst),
+            None => dst.push_str(template),
+        }
+        dst
+    }
+
+    /// `haystack` with every match replaced by the expansion of `template`.
+    pub fn replace_all(&self, haystack: &str, template: &str) -> String {
+        let mut out = String::with_capacity(haystack.len());
+        let mut last = 0;
+        for (start, end) in self.find_iter(haystack) {
+            out.push_str(&haystack[last..start]);
+            out.push_str(&self.expand(haystack, start, end, template));
+            last = end;
+        }
+        out.push_str(&haystack[last..]);
+        out
+    }
+
+    /// Successive non-overlapping matches, left to right.
+    pub fn find_iter<'a>(&'a self, haystack: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let mut at = 0;
+        std::iter::from_fn(move || {
+            if at > haystack.len() {
+                return None;
+            }
+            let (start, end) = self.find_at(haystack, at)?;
+            at = if end > start {
+                end
+            } else {
+                // Step over the character after an empty match.
+                end + haystack[end..].chars().next().map_or(1, char::len_utf8)
+            };
+            Some((start, end))
+        })
+    }
+}
+
+impl Engine {
+    fn new(patterns: &[String], opts: Options) -> Result<Self, String> {
+        // The literal matcher folds ASCII only; leave other `-F -i`
+        // patterns to a Unicode-aware engine.
+        let ascii_fold = !opts.ignore_case
+            || !cfg!(feature = "unicode")
+            || patterns.iter().all(|p| p.is_ascii());
+        if opts.fixed && !opts.word && !opts.line && ascii_fold && !opts.captures {
+            return Ok(Engine::Literals(AhoCorasick::new(
+                patterns,
+                opts.ignore_case,
+            )));
+        }
+        if patterns.is_empty() {
+            // `-f /dev/null`: nothing can match.
+            return Ok(Engine::Literals(AhoCorasick::new::<&str>(&[], false)));
+        }
+        let translated: Vec<String> = if opts.fixed {
+            // Longest first, so the engine's leftmost-first alternation
+            // prefers the longest literal like the Aho-Corasick matcher.
+            let mut literals: Vec<&String> = patterns.iter().collect();
+            literals.sort_by_key(|p| std::cmp::Reverse(p.len()));
+            literals.into_iter().map(|p| escape(p)).collect()
+        } else {
+            patterns
+                .iter()
+                .map(|p| {
+                    syntax::translate(p, opts.syntax, opts.multiline)
+                        .map_err(|e| format!("invalid regex '{p}': {e}"))
+                })
+                .collect::<Result<_, _>>()?
+        };
+        let source = match translated.as_slice() {
+            [single] => single.clone(),
+            _ => translated
+                .iter()
+                .map(|p| format!("(?:{p})"))
+                .collect::<Vec<_>>()
+                .join("|"),
+        };
+        let build = |source: &str| {
+            RegexBuilder::new(source)
+                .case_insensitive(opts.ignore_case)
+                .multi_line(opts.multiline)
+                .crlf(opts.multiline)
+                .build()
+                .map_err(|e| match patterns {
+                    [single] => format!("invalid regex '{single}': {e}"),
+                    _ => format!("invalid regex: {e}"),
+                })
+        };
+
+        if opts.line {
+            return build(&format!("^(?:{source})$")).map(Engine::Regex);
+        }
+        let re = build(&source)?;
+        if opts.word {
+            let exact = build(&format!("^(?:{source})$"))?;
+            return Ok(Engine::Words(WordMatcher { re, exact }));
+        }
+        Ok(Engine::Regex(re))
+    }
+}
+
+/// Restricts matches to whole words, following GNU grep's rules: a word
+/// character is a letter, digit or underscore, and a match must not have one
+/// directly before or after it.
+pub struct WordMatcher {
+    re: Regex,
+    // The same patterns anchored at both ends, to test shorter candidates.
+    exact: Regex,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn word_start(haystack: &str, pos: usize) -> bool {
+    !haystack[..pos]
+        .chars()
+        .next_back()
+        .is_some_and(is_word_char)
+}
+
+fn word_end(haystack: &str, pos: usize) -> bool {
+    !haystack[pos..].chars().next().is_some_and(is_word_char)
+}
+
+impl WordMatcher {
+    fn find_at(&self, haystack: &str, mut at: usize) -> Option<(usize, usize)> {
+        while at <= haystack.len() {
+            let m = self.re.find_at(haystack, at)?;
+            let (start, end) = (m.start(), m.end());
+            if word_start(haystack, start) {
+                if word_end(haystack, end) {
+                    return Some((start, end));
+                }
+                // Like GNU grep, try a shorter match at the same start before
+                // moving on: `-w 'foo[a-z ]*'` matches "foo" in "foo bar_".
+                let shorter = (start..end).rev().find(|&e| {
+                    haystack.is_char_boundary(e)
+                        && word_end(haystack, e)
+                        && self.exact.is_match(&haystack[start..e])
+                });
+                if let Some(e) = shorter {
+                    return Some((start, e));
+                }
+            }
+            // Then retry one character further on.
+            at = start + haystack[start..].chars().next().map_or(1, char::len_utf8);
+        }
+        None
+    }
+}
+
+/// Splits a `-e` argument on newlines, as GNU grep does: each line is a
+/// separate pattern, and an empty argument is one empty pattern.
+pub fn split_patterns(text: &str) -> Vec<String> {
+    if text.is_empty() {
+        return vec![String::new()];
+    }
+    text.lines().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(ps: &[&str]) -> Vec<String> {
+        ps.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn fixed() -> Options {
+        Options {
+            fixed: true,
+            ..Options::default()
+        }
+    }
+
+    fn words(ps: &[&str], fixed: bool) -> Matcher {
+        let opts = Options {
+            fixed,
+            word: true,
+            ..Options::default()
+        };
+        Matcher::new(&patterns(ps), opts).unwrap()
+    }
+
+    #[test]
+    fn multiple_regexes_are_alternated() {
+        let m = Matcher::new(&patterns(&["^a", "b$"]), Options::default()).unwrap();
+        assert!(m.is_match("ax"));
+        assert!(m.is_match("xb"));
+        assert!(!m.is_match("xa"));
+    }
+
+    #[test]
+    fn fixed_strings_are_literal() {
+        let m = Matcher::new(&patterns(&["a.b[0]", "x*"]), fixed()).unwrap();
+        assert!(m.is_match("let v = a.b[0];"));
+        assert!(m.is_match("x*y"));
+        assert!(!m.is_match("axb[0] xx"));
+    }
+
+    #[test]
+    fn no_patterns_match_nothing() {
+        for fixed in [false, true] {
+            let opts = Options {
+                fixed,
+                ..Options::default()
+            };
+            let m = Matcher::new(&[], opts).unwrap();
+            assert!(!m.is_match(""));
+            assert!(!m.is_match("abc"));
+        }
+    }
+
+    #[test]
+    fn find_iter_steps_over_empty_matches() {
+        let m = Matcher::new(&patterns(&["é*"]), Options::default()).unwrap();
+        let found: Vec<_> = m.find_iter("aéé").collect();
+        assert_eq!(found, vec![(0, 0), (1, 5), (5, 5)]);
+    }
+
+    #[test]
+    fn invalid_regex_is_reported() {
+        let err = Matcher::new(&patterns(&["[invalid"]), Options::default())
+            .err()
+            .unwrap();
+        assert!(err.starts_with("invalid regex '[invalid'"));
+    }
+
+    #[test]
+    fn word_rejects_partial_words() {
+        let m = words(&["id"], false);
+        assert!(!m.is_match("valid"));
+        assert!(!m.is_match("id_x"));
+        assert_eq!(m.find_at("valid id", 0), Some((6, 8)));
+        assert_eq!(m.find_at("(id)", 0), Some((1, 3)));
+    }
+
+    #[test]
+    fn word_retries_after_failed_boundary() {
+        // The leftmost match fails the check; a later one passes.
+        assert_eq!(words(&["ab"], false).find_at("abc ab", 0), Some((4, 6)));
+        // A shorter match at the same start passes.
+        assert_eq!(
+            words(&["foo[a-z ]*"], false).find_at("foo bar_", 0),
+            Some((0, 3))
+        );
+        assert_eq!(words(&[r"a\+"], false).find_at("aab", 0), None);
+    }
+
+    #[test]
+    fn word_uses_unicode_letters() {
+        let m = words(&["caf"], false);
+        assert!(!m.is_match("café"));
+        assert!(words(&["café"], false).is_match("un café noir"));
+    }
+
+    #[test]
+    fn word_with_fixed_and_ignore_case() {
+        let opts = Options {
+            fixed: true,
+            ignore_case: true,
+            word: true,
+            ..Options::default()
+        };
+        let m = Matcher::new(&patterns(&["a.b"]), opts).unwrap();
+        assert!(m.is_match("x A.B y"));
+        assert!(!m.is_match("x a.bc"));
+        assert!(!m.is_match("x axb"));
+        let found: Vec<_> = m.find_iter("a.b a.bb a.b").collect();
+        assert_eq!(found, vec![(0, 3), (9, 12)]);
+    }
+
+    #[test]
+    fn line_matches_whole_line() {
+        let opts = Options {
+            line: true,
+            ..Options::default()
+        };
+        let m = Matcher::new(&patterns(&[r"ab\|cd"]), opts).unwrap();
+        assert!(m.is_match("ab"));
+        assert!(m.is_match("cd"));
+        assert!(!m.is_match("abcd"));
+
+        let opts = Options {
+            fixed: true,
+            line: true,
+            ..Options::default()
+        };
+        let m = Matcher::new(&patterns(&["a.b", "x"]), opts).unwrap();
+        assert!(m.is_match("a.b"));
+        assert!(!m.is_match("a.b "));
+        assert!(!m.is_match("axb"));
+    }
+
+    #[test]
+    fn basic_and_extended_syntax() {
+        let m = Matcher::new(&patterns(&["a|b+"]), Options::default()).unwrap();
+        assert!(m.is_match("xa|b+"));
+        assert!(!m.is_match("abb"));
+        let opts = Options {
+            syntax: Syntax::Extended,
+            ..Options::default()
+        };
+        let m = Matcher::new(&patterns(&["a|b+"]), opts).unwrap();
+        assert!(m.is_match("bb"));
+        let err = Matcher::new(&patterns(&[r"\(a\)\1"]), Options::default())
+            .err()
+            .unwrap();
+        assert!(err.contains("back-references"), "{err}");
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn unicode_case_folding_and_classes() {
+        let icase = |fixed| Options {
+            fixed,
+            ignore_case: true,
+            ..Options::default()
+        };
+        for fixed in [false, true] {
+            let m = Matcher::new(&patterns(&["ÉTÉ"]), icase(fixed)).unwrap();
+            assert!(m.is_match("un été chaud"));
+            let m = Matcher::new(&patterns(&["straße"]), icase(fixed)).unwrap();
+            assert!(m.is_match("STRAẞE"));
+        }
+        let m = Matcher::new(&patterns(&[r"^\w\+$"]), Options::default()).unwrap();
+        assert!(m.is_match("Größe"));
+        let m = Matcher::new(&patterns(&[r"\bété\b"]), Options::default()).unwrap();
+        assert!(m.is_match("l'été dernier"));
+        assert!(!m.is_match("étéx"));
+        let m = Matcher::new(
+            &patterns(&["été"]),
+            Options {
+                word: true,
+                ignore_case: true,
+                ..Options::default()
+            },
+        )
+        .unwrap();
+        assert!(m.is_match("ÉTÉ 2024"));
+        assert!(!m.is_match("ÉTÉS"));
+    }
+
+    #[test]
+    fn replace_expands_capture_groups() {
+        let opts = Options {
+            syntax: Syntax::Extended,
+            captures: true,
+            ..Options::default()
+        };
+        let m = Matcher::new(&patterns(&[r"(\w+)=(?<v>[0-9]+)"]), opts).unwrap();
+        assert_eq!(m.replace_all("a=1, b=22", "${v}<-$1"), "1<-a, 22<-b");
+        assert_eq!(m.replace_all("none", "$1"), "none");
+
+        let m = Matcher::new(&patterns(&["^"]), opts).unwrap();
+        assert_eq!(m.replace_all("x", "> "), "> x");
+
+        let fixed = Options {
+            fixed: true,
+            captures: true,
+            ..Options::default()
+        };
+        let m = Matcher::new(&patterns(&["ab", "abcd"]), fixed).unwrap();
+        assert_eq!(m.replace_all("abcde ab", "[$0]"), "[abcd]e [ab]");
+
+        let words = Options {
+            word: true,
+            captures: true,
+            ..opts
+        };
+        let m = Matcher::new(&patterns(&["(foo)[a-z ]*"]), words).unwrap();
+        assert_eq!(m.replace_all("foo bar_", "<$0|$1>"), "<foo|foo> bar_");
+    }
+
+    #[test]
+    fn split_on_newlines() {
+        assert_eq!(split_patterns("a\r\nb\n"), vec!["a", "b"]);
+        assert_eq!(split_patterns("a\n\nb"), vec!["a", "", "b"]);
+        assert_eq!(split_patterns(""), vec![""]);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::aho_corasick::AhoCorasick;
+
+/// Finds lines that may match before the regex engine sees them: every
+/// match of grep's patterns contains one of a set of literals, so a buffer
+/// without any of them holds no matching line.
+pub enum Prefilter {
+    Single(Finder),
+    Many(AhoCorasick),
+}
+
+impl Prefilter {
+    /// `literals` holds one required literal per pattern. Returns None if
+    /// any is empty, as that pattern can match anywhere.
+    pub fn new(literals: &[String], ignore_case: bool) -> Option<Self> {
+        if literals.is_empty() || literals.iter().any(|l| l.is_empty()) {
+            return None;
+        }
+        Some(match literals {
+            [single] => Prefilter::Single(Finder::new(single.as_bytes(), ignore_case)),
+            _ => Prefilter::Many(AhoCorasick::new(literals, ignore_case)),
+        })
+    }
+
+    /// The start of the first occurrence of any literal in `haystack`.
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        match self {
+            Prefilter::Single(f) => f.find(haystack),
+            Prefilter::Many(ac) => ac.find_bytes(haystack, 0).map(|(start, _)| start),
+        }
+    }
+}
+
+/// Substring search for one literal: finds its rarest byte with a
+/// word-at-a-time scan, then checks the literal around it.
+pub struct Finder {
+    needle: Vec<u8>,
+    ignore_case: bool,
+    // Index in `needle` of the byte to scan for.
+    rare: usize,
+}
+
+impl Finder {
+    fn new(needle: &[u8], ignore_case: bool) -> Self {
+        let rare = (0..needle.len())
+            .min_by_key(|&i| frequency(needle[i].to_ascii_lowercase()))
+            .unwrap_or(0);
+        Finder {
+            needle: needle.to_vec(),
+            ignore_case,
+            rare,
+        }
+    }
+
+    fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let byte = self.needle[self.rare];
+        let (lower, upper) = if self.ignore_case {
+            (byte.to_ascii_lowercase(), byte.to_ascii_uppercase())
+        } else {
+            (byte, byte)
+        };
+        let last_start = haystack.len().checked_sub(self.needle.len())?;
+        let mut at = self.rare;
+        while at < haystack.len() {
+            let found = at + memchr2(lower, upper, &haystack[at..])?;
+            let start = found - self.rare;
+            if start > last_start {
+                return None;
+            }
+            let candidate = &haystack[start..start + self.needle.len()];
+            let equal = if self.ignore_case {
+                candidate.eq_ignore_ascii_case(&self.needle)
+            } else {
+                candidate == self.needle.as_slice()
+            };
+            if equal {
+                return Some(start);
+            }
+            at = found + 1;
+        }
+        None
+    }
+}
+
+/// A rough rank of how common a byte is in text and source code, so the
+/// scan looks for one that rarely turns up.
+fn frequency(b: u8) -> u8 {
+    match b {
+        b' ' | b'e' | b't' | b'a' | b'o' | b'i' | b'n' | b's' | b'r' => 5,
+        b'a'..=b'z' => 4,
+        b'\t' | b'.' | b',' | b'(' | b')' | b'_' | b'=' | b';' | b'"' | b'/' | b'-' => 3,
+        b'0'..=b'9' | b'A'..=b'Z' | b':' | b'{' | b'}' | b'\'' | b'*' | b'<' | b'>' => 2,
+        0x80..=0xFF => 1,
+        _ => 0,
+    }
+}
+
+const LO: u64 = u64::from_ne_bytes([0x01; 8]);
+const HI: u64 = u64::from_ne_bytes([0x80; 8]);
+
+/// True if any byte of `v` is zero.
+fn has_zero(v: u64) -> bool {
+    v.wrapping_sub(LO) & !v & HI != 0
+}
+
+/// The index of the first `a` or `b` in `haystack`, testing eight bytes at
+/// a time.
+pub fn memchr2(a: u8, b: u8, haystack: &[u8]) -> Option<usize> {
+    let (va, vb) = (LO * u64::from(a), LO * u64::from(b));
+    let mut chunks = haystack.chunks_exact(8);
+    let mut offset = 0;
+    for chunk in &mut chunks {
+        let v = u64::from_ne_bytes(chunk.try_into().unwrap());
+        if has_zero(v ^ va) || has_zero(v ^ vb) {
+            break;
+        }
+        offset += 8;
+    }
+    haystack[offset..]
+        .iter()
+        .position(|&c| c == a || c == b)
+        .map(|i| offset + i)
+}
+
+pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|&c| c == needle)
+}
+
+/// How many times `needle` occurs in `haystack`.
+pub fn count(needle: u8, haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&c| c == needle).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literals(ls: &[&str]) -> Vec<String> {
+        ls.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn memchr2_finds_first_byte_in_any_lane() {
+        let hay: Vec<u8> = (0..40).map(|i| b'a' + i % 20).collect();
+        for (i, &b) in hay.iter().enumerate().take(20) {
+            assert_eq!(memchr2(b, b, &hay), Some(i));
+        }
+        assert_eq!(memchr2(b'z', b'z', &hay), None);
+        assert_eq!(memchr2(b'z', b'c', &hay), Some(2));
+        assert_eq!(memchr2(b'x', b'y', b""), None);
+        assert_eq!(memrchr(b'a', &hay), Some(20));
+        assert_eq!(count(b'a', &hay), 2);
+    }
+
+    #[test]
+    fn single_literal() {
+        let p = Prefilter::new(&literals(&["needle"]), false).unwrap();
+        assert_eq!(p.find(b"hay needle hay"), Some(4));
+        assert_eq!(p.find(b"needl needle"), Some(6));
+        assert_eq!(p.find(b"hay NEEDLE hay"), None);
+        assert_eq!(p.find(b"needl"), None);
+        // The rare byte near the end of the haystack, literal cut short.
+        assert_eq!(p.find(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaneedl"), None);
+
+        let p = Prefilter::new(&literals(&["Needle"]), true).unwrap();
+        assert_eq!(p.find(b"hay nEEDLE hay"), Some(4));
+    }
+
+    #[test]
+    fn several_literals() {
+        let p = Prefilter::new(&literals(&["foo", "bar"]), false).unwrap();
+        assert_eq!(p.find(b"xx bar foo"), Some(3));
+        assert_eq!(p.find(b"xx baz fo"), None);
+    }
+
+    #[test]
+    fn empty_literal_disables_the_prefilter() {
+        assert!(Prefilter::new(&literals(&["foo", ""]), false).is_none());
+        assert!(Prefilter::new(&[], false).is_none());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::matcher::Matcher;
+use crate::stats::FileCounts;
+use crate::{trim_line_ending, BinaryFiles, Opts};
+use common::encoding;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// `--in-place`: replaces the matches on each selected line of `path`,
+/// keeping the file's encoding, byte-order mark and line endings. Returns
+/// whether any line was selected.
+pub fn rewrite_file(
+    path: &str,
+    matcher: &Matcher,
+    opts: &Opts,
+    counts: &mut FileCounts,
+) -> io::Result<bool> {
+    let bytes = fs::read(path)?;
+    counts.bytes = bytes.len() as u64;
+    let bom = encoding::sniff_bom(&bytes);
+    let mut text = String::new();
+    // Lossy decoding would write U+FFFD over the original bytes.
+    encoding::open_reader(&bytes[..])
+        .read_to_string(&mut text)
+        .map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "not valid UTF-8; not rewritten")
+        })?;
+    if opts.binary_files != BinaryFiles::Text && encoding::is_binary(text.as_bytes()) {
+        counts.binary = true;
+        return Ok(false);
+    }
+
+    let template = opts.replace.as_deref().unwrap_or_default();
+    if opts.multiline {
+        let new_text = matcher.replace_all(&text, template);
+        if opts.stats.is_some() {
+            counts.matches = matcher.find_iter(&text).count() as u64;
+        }
+        if new_text != text {
+            write_atomically(Path::new(path), &encoding::encode(&new_text, bom))?;
+        }
+        return Ok(matcher.is_match(&text));
+    }
+    let mut selected = 0;
+    let mut new_text = String::with_capacity(text.len());
+    for piece in text.split_inclusive('\n') {
+        let line = trim_line_ending(piece);
+        let limit_reached = opts.max_count.is_some_and(|max| selected >= max);
+        if !limit_reached && matcher.is_match(line) != opts.invert {
+            selected += 1;
+            counts.lines += 1;
+            if opts.stats.is_some() {
+                counts.matches += matcher.find_iter(line).count() as u64;
+            }
+            new_text.push_str(&matcher.replace_all(line, template));
+            new_text.push_str(&piece[line.len()..]);
+        } else {
+            new_text.push_str(piece);
+        }
+    }
+    if new_text != text {
+        write_atomically(Path::new(path), &encoding::encode(&new_text, bom))?;
+    }
+    Ok(selected > 0)
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it over
+/// `path`, so readers see either the old file or the new one in full. A
+/// symlink is followed, and its target replaced. Read-only files are
+/// refused: Windows won't rename over them.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let target = fs::canonicalize(path)?;
+    if fs::metadata(&target)?.permissions().readonly() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "file is read-only; not rewritten",
+        ));
+    }
+    let dir = target.parent().unwrap_or(Path::new("."));
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dir.join(format!(".{name}.{}.tmp", std::process::id()));
+    let result = (|| {
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        fs::set_permissions(&tmp, fs::metadata(&target)?.permissions())?;
+        fs::rename(&tmp, &target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::{self, Options};
+
+    fn rewrite(pattern: &str, template: &str, contents: &[u8]) -> Vec<u8> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f.txt");
+        fs::write(&path, contents).unwrap();
+        let opts = Opts {
+            replace: Some(template.to_string()),
+            in_place: true,
+            ..Opts::default()
+        };
+        let m = Matcher::new(
+            &[pattern.to_string()],
+            Options {
+                syntax: crate::syntax::Syntax::Extended,
+                captures: true,
+                ..matcher::Options::default()
+            },
+        )
+        .unwrap();
+        rewrite_file(
+            path.to_str().unwrap(),
+            &m,
+            &opts,
+            &mut FileCounts::default(),
+        )
+        .unwrap();
+        let leftovers = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(leftovers, 1, "temporary file left behind");
+        fs::read(&path).unwrap()
+    }
+
+    #[test]
+    fn keeps_line_endings() {
+        assert_eq!(
+            rewrite("(\\w+)@old", "$1@new", b"a@old\r\nb\nc@old"),
+            b"a@new\r\nb\nc@new"
+        );
+    }
+
+    #[test]
+    fn keeps_bom_and_utf16() {
+        let original = encoding::encode("id: old\r\n", Some(encoding::Bom::Utf16Le));
+        let expected = encoding::encode("id: new\r\n", Some(encoding::Bom::Utf16Le));
+        assert_eq!(rewrite("old", "new", &original), expected);
+
+        let original = encoding::encode("old\n", Some(encoding::Bom::Utf8));
+        assert_eq!(rewrite("old", "new", &original), b"\xEF\xBB\xBFnew\n");
+    }
+
+    #[test]
+    fn leaves_invalid_utf8_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f.txt");
+        fs::write(&path, b"caf\xE9 old\n").unwrap();
+        let opts = Opts {
+            replace: Some("new".to_string()),
+            ..Opts::default()
+        };
+        let m = Matcher::new(&["old".to_string()], Options::default()).unwrap();
+        let err = rewrite_file(
+            path.to_str().unwrap(),
+            &m,
+            &opts,
+            &mut FileCounts::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read(&path).unwrap(), b"caf\xE9 old\n");
+    }
+
+    #[test]
+    fn refuses_read_only_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f.txt");
+        fs::write(&path, "old\n").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+        let err = write_atomically(&path, b"new\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(fs::read(&path).unwrap(), b"old\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
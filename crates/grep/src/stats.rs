@@ -0,0 +1,173 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "invalid argument '{s}' for --stats (expected 'text' or 'json')"
+            )),
+        }
+    }
+}
+
+/// What searching one file (or stdin) found, added to [`STATS`] when the
+/// search ends, however it ends.
+#[derive(Default)]
+pub struct FileCounts {
+    pub bytes: u64,
+    pub lines: u64,
+    pub matches: u64,
+    pub binary: bool,
+}
+
+/// `--stats` counters, shared by the walker and the search threads. They
+/// stay untouched unless [`Stats::enable`] was called, so searches without
+/// `--stats` don't contend on them.
+pub struct Stats {
+    enabled: AtomicBool,
+    searched: AtomicU64,
+    binary: AtomicU64,
+    excluded: AtomicU64,
+    unreadable: AtomicU64,
+    bytes: AtomicU64,
+    lines: AtomicU64,
+    matches: AtomicU64,
+}
+
+pub static STATS: Stats = Stats::new();
+
+impl Stats {
+    const fn new() -> Self {
+        Stats {
+            enabled: AtomicBool::new(false),
+            searched: AtomicU64::new(0),
+            binary: AtomicU64::new(0),
+            excluded: AtomicU64::new(0),
+            unreadable: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            lines: AtomicU64::new(0),
+            matches: AtomicU64::new(0),
+        }
+    }
+
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn searched(&self, counts: &FileCounts) {
+        if !self.enabled() {
+            return;
+        }
+        self.searched.fetch_add(1, Ordering::Relaxed);
+        self.binary
+            .fetch_add(u64::from(counts.binary), Ordering::Relaxed);
+        self.bytes.fetch_add(counts.bytes, Ordering::Relaxed);
+        self.lines.fetch_add(counts.lines, Ordering::Relaxed);
+        self.matches.fetch_add(counts.matches, Ordering::Relaxed);
+    }
+
+    /// A file or directory skipped by a filter, an ignore rule, or because
+    /// it is hidden or a link.
+    pub fn excluded(&self) {
+        if !self.enabled() {
+            return;
+        }
+        self.excluded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn unreadable(&self) {
+        if !self.enabled() {
+            return;
+        }
+        self.unreadable.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn report(&self, elapsed: Duration, format: Format) -> String {
+        let fields = [
+            ("files_searched", "files searched", &self.searched),
+            ("files_binary", "binary files", &self.binary),
+            (
+                "files_excluded",
+                "files and directories excluded",
+                &self.excluded,
+            ),
+            (
+                "files_unreadable",
+                "unreadable files and directories",
+                &self.unreadable,
+            ),
+            ("bytes_read", "bytes read", &self.bytes),
+            ("matched_lines", "lines selected", &self.lines),
+            ("matches", "matches", &self.matches),
+        ];
+        let secs = elapsed.as_secs_f64();
+        let mut out = String::new();
+        match format {
+            Format::Text => {
+                for (_, label, value) in fields {
+                    let _ = writeln!(out, "{} {label}", value.load(Ordering::Relaxed));
+                }
+                let _ = writeln!(out, "{secs:.6} seconds elapsed");
+            }
+            Format::Json => {
+                out.push_str("{\"type\":\"summary\"");
+                for (key, _, value) in fields {
+                    let _ = write!(out, ",\"{key}\":{}", value.load(Ordering::Relaxed));
+                }
+                let _ = writeln!(out, ",\"elapsed_secs\":{secs:.6}}}");
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_formats() {
+        let stats = Stats::new();
+        // Nothing is counted until enabled.
+        stats.excluded();
+        stats.enable();
+        stats.searched(&FileCounts {
+            bytes: 120,
+            lines: 3,
+            matches: 4,
+            binary: false,
+        });
+        stats.searched(&FileCounts {
+            binary: true,
+            ..FileCounts::default()
+        });
+        stats.excluded();
+        let elapsed = Duration::from_millis(1500);
+
+        let text = stats.report(elapsed, Format::Text);
+        assert!(text.starts_with("2 files searched\n1 binary files\n1 files and"));
+        assert!(text.contains("\n120 bytes read\n3 lines selected\n4 matches\n"));
+        assert!(text.ends_with("\n1.500000 seconds elapsed\n"));
+
+        assert_eq!(
+            stats.report(elapsed, Format::Json),
+            "{\"type\":\"summary\",\"files_searched\":2,\"files_binary\":1,\
+             \"files_excluded\":1,\"files_unreadable\":0,\"bytes_read\":120,\
+             \"matched_lines\":3,\"matches\":4,\"elapsed_secs\":1.500000}\n"
+        );
+    }
+}
@@ -0,0 +1,545 @@
+use crate::matcher::escape;
+
+/// The POSIX regular expression dialects accepted by GNU grep.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Syntax {
+    /// `-G`: `\(`, `\)`, `\{`, `\}`, `\|`, `\+` and `\?` are operators and
+    /// the unescaped characters are literal.
+    #[default]
+    Basic,
+    /// `-E`: `(`, `)`, `{`, `}`, `|`, `+` and `?` are operators.
+    Extended,
+}
+
+const POSIX_CLASSES: &[&str] = &[
+    "alnum", "alpha", "blank", "cntrl", "digit", "graph", "lower", "print", "punct", "space",
+    "upper", "xdigit",
+];
+
+/// Rewrites a GNU basic or extended regular expression into the syntax of
+/// the regex engine, keeping GNU's rules for where `*`, `^` and `$` are
+/// literal. With `multiline` (`-U`), `\n` and `\r` match a line feed and a
+/// carriage return.
+pub fn translate(pattern: &str, syntax: Syntax, multiline: bool) -> Result<String, String> {
+    Translator::new(pattern, syntax, multiline)
+        .run()
+        .map(|t| t.out)
+}
+
+/// The longest string that every match of `pattern` contains, for the
+/// prefilter: the longest run of literal characters outside any group,
+/// alternation or quantifier. None if there is no such run, or if inline
+/// flags could change what the characters match.
+pub fn required_literal(pattern: &str, syntax: Syntax) -> Option<String> {
+    let t = Translator::new(pattern, syntax, false).run().ok()?;
+    if t.alternated || t.flagged {
+        return None;
+    }
+    let longest = std::cmp::max_by_key(t.longest, t.run, |s| s.len());
+    (!longest.is_empty()).then_some(longest)
+}
+
+struct Translator {
+    chars: Vec<char>,
+    pos: usize,
+    ere: bool,
+    multiline: bool,
+    out: String,
+    // Where the last quantifiable item starts in `out`.
+    atom: Option<usize>,
+    // Whether that item already has a quantifier.
+    repeated: bool,
+    // Where each open group starts in `out`.
+    groups: Vec<usize>,
+    // For `required_literal`: the literal characters just translated
+    // outside any group, and the longest earlier such run.
+    run: String,
+    longest: String,
+    // Whether the last item is the last character of `run`.
+    in_run: bool,
+    alternated: bool,
+    flagged: bool,
+}
+
+impl Translator {
+    fn new(pattern: &str, syntax: Syntax, multiline: bool) -> Self {
+        Translator {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            ere: syntax == Syntax::Extended,
+            multiline,
+            out: String::new(),
+            atom: None,
+            repeated: false,
+            groups: Vec::new(),
+            run: String::new(),
+            longest: String::new(),
+            in_run: false,
+            alternated: false,
+            flagged: false,
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied();
+        self.pos += 1;
+        c
+    }
+
+    fn peek_is(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(k, c)| self.chars.get(self.pos + k) == Some(&c))
+    }
+
+    fn literal(&mut self, c: char) {
+        self.atom = Some(self.out.len());
+        self.repeated = false;
+        self.out.push_str(&escape(c.encode_utf8(&mut [0; 4])));
+        self.in_run = self.groups.is_empty();
+        if self.in_run {
+            self.run.push(c);
+        }
+    }
+
+    fn item(&mut self, s: &str) {
+        self.end_run();
+        self.atom = Some(self.out.len());
+        self.repeated = false;
+        self.out.push_str(s);
+    }
+
+    fn anchor(&mut self, s: &str) {
+        self.end_run();
+        self.atom = None;
+        self.out.push_str(s);
+    }
+
+    fn end_run(&mut self) {
+        self.in_run = false;
+        if self.run.len() > self.longest.len() {
+            self.longest = std::mem::take(&mut self.run);
+        } else {
+            self.run.clear();
+        }
+    }
+
+    fn repeat(&mut self, op: &str) {
+        let Some(start) = self.atom else {
+            return;
+        };
+        // The quantified character may repeat or be missing.
+        if self.in_run {
+            self.run.pop();
+            self.end_run();
+        }
+        // `a**` or `a*{2}`: the engine rejects (or reads as lazy) stacked
+        // quantifiers, so group the quantified item first.
+        if self.repeated {
+            self.out.insert_str(start, "(?:");
+            self.out.push(')');
+        }
+        self.out.push_str(op);
+        self.repeated = true;
+    }
+
+    /// True if `c` (escaped or not) is an operator in this dialect.
+    fn is_op(&self, c: char, escaped: bool) -> bool {
+        "(){}|+?".contains(c) && escaped != self.ere
+    }
+
+    fn run(mut self) -> Result<Self, String> {
+        // At the start of the pattern, a group or an alternative.
+        let mut start = true;
+        while let Some(c) = self.next() {
+            let at_start = std::mem::replace(&mut start, false);
+            let (c, escaped) = if c == '\\' {
+                match self.next() {
+                    Some(n) => (n, true),
+                    None => return Err("trailing backslash (\\)".to_string()),
+                }
+            } else {
+                (c, false)
+            };
+
+            match c {
+                // Flags such as `(?s)` (dot matches newline, for `-U`) or
+                // `(?i)` apply to the rest of the pattern or group.
+                '(' if !escaped && self.peek_flags(')') => {
+                    self.flagged = true;
+                    let flags = self.take_flags();
+                    self.anchor(&format!("({flags}"));
+                    start = at_start;
+                }
+                '(' if self.is_op(c, escaped) => {
+                    self.groups.push(self.out.len());
+                    self.anchor("(");
+                    // `(?<name>...)` or `(?P<name>...)` names the group,
+                    // for `--replace` templates; `(?s:...)` sets flags.
+                    if let Some(name) = self.group_name() {
+                        self.out.push_str(&format!("?P<{name}>"));
+                    } else if self.peek_flags(':') {
+                        self.flagged = true;
+                        let flags = self.take_flags();
+                        self.out.push_str(&flags);
+                    }
+                    start = true;
+                }
+                ')' if self.is_op(c, escaped) => match self.groups.pop() {
+                    Some(open) => {
+                        self.out.push(')');
+                        self.atom = Some(open);
+                        self.repeated = false;
+                    }
+                    None if self.ere => self.literal(')'),
+                    None => return Err("unmatched \\)".to_string()),
+                },
+                '|' if self.is_op(c, escaped) => {
+                    self.alternated |= self.groups.is_empty();
+                    self.anchor("|");
+                    start = true;
+                }
+                '{' if self.is_op(c, escaped) && self.atom.is_some() => match self.interval() {
+                    Some(op) => self.repeat(&op),
+                    None if self.ere => self.literal('{'),
+                    None => return Err("invalid content of \\{\\}".to_string()),
+                },
+                '+' | '?' if self.is_op(c, escaped) && self.atom.is_some() => {
+                    self.repeat(&c.to_string())
+                }
+                '*' if !escaped && !at_start && self.atom.is_some() => self.repeat("*"),
+                '^' if !escaped && (self.ere || at_start) => {
+                    self.anchor("^");
+                    start = true;
+                }
+                '$' if !escaped && (self.ere || self.at_basic_end()) => self.anchor("$"),
+                '.' if !escaped => self.item("."),
+                '[' if !escaped => {
+                    let class = self.bracket()?;
+                    self.item(&class);
+                }
+                '1'..='9' if escaped => {
+                    return Err(format!("back-references (\\{c}) are not supported"));
+                }
+                '<' | '>' | 'b' | 'B' if escaped => self.anchor(&format!("\\{c}")),
+                'w' | 'W' | 's' | 'S' if escaped => self.item(&format!("\\{c}")),
+                'n' | 'r' if escaped && self.multiline => self.item(&format!("\\{c}")),
+                '`' if escaped => self.anchor("\\A"),
+                '\'' if escaped => self.anchor("\\z"),
+                c => self.literal(c),
+            }
+        }
+        if !self.groups.is_empty() {
+            return Err(if self.ere {
+                "unmatched (".to_string()
+            } else {
+                "unmatched \\(".to_string()
+            });
+        }
+        Ok(self)
+    }
+
+    /// True if `?flags` then `end` follow, as in `(?is)` or `(?s:`.
+    fn peek_flags(&self, end: char) -> bool {
+        let rest = self.chars.get(self.pos..).unwrap_or_default();
+        let len = rest
+            .iter()
+            .skip(1)
+            .take_while(|c| "imsxU-".contains(**c))
+            .count();
+        rest.first() == Some(&'?') && len > 0 && rest.get(len + 1) == Some(&end)
+    }
+
+    /// Consumes what `peek_flags` saw, returning it with its end.
+    fn take_flags(&mut self) -> String {
+        let from = self.pos;
+        while self.chars[self.pos] != ')' && self.chars[self.pos] != ':' {
+            self.pos += 1;
+        }
+        self.pos += 1;
+        self.chars[from..self.pos].iter().collect()
+    }
+
+    fn group_name(&mut self) -> Option<String> {
+        let prefix = ["?P<", "?<"].into_iter().find(|p| self.peek_is(p))?;
+        let from = self.pos + prefix.len();
+        let len = self.chars[from..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+            .count();
+        if len == 0 || self.chars.get(from + len) != Some(&'>') {
+            return None;
+        }
+        self.pos = from + len + 1;
+        Some(self.chars[from..from + len].iter().collect())
+    }
+
+    /// In a basic regex, `$` is an anchor only at the end of the pattern, a
+    /// group or an alternative.
+    fn at_basic_end(&self) -> bool {
+        self.pos >= self.chars.len() || self.peek_is("\\)") || self.peek_is("\\|")
+    }
+
+    /// Parses `m}`, `m,}`, `,n}` or `m,n}` (with `\}` in a basic regex)
+    /// after an opening brace, returning the engine's quantifier. Leaves
+    /// the position alone if it is not a valid interval.
+    fn interval(&mut self) -> Option<String> {
+        let saved = self.pos;
+        let digits = |t: &mut Self| {
+            let from = t.pos;
+            while t.chars.get(t.pos).is_some_and(|c| c.is_ascii_digit()) {
+                t.pos += 1;
+            }
+            t.chars[from..t.pos].iter().collect::<String>()
+        };
+        let min = digits(self);
+        let comma = self.peek_is(",");
+        if comma {
+            self.pos += 1;
+        }
+        let max = if comma { digits(self) } else { String::new() };
+        let close = if self.ere { "}" } else { "\\}" };
+        let valid = (!min.is_empty() || !max.is_empty())
+            && self.peek_is(close)
+            && match (min.parse::<u32>(), max.parse::<u32>()) {
+                (Ok(lo), Ok(hi)) => lo <= hi,
+                _ => true,
+            };
+        if !valid {
+            self.pos = saved;
+            return None;
+        }
+        self.pos += close.len();
+        let min = if min.is_empty() { "0" } else { &min };
+        Some(match (comma, max.is_empty()) {
+            (false, _) => format!("{{{min}}}"),
+            (true, true) => format!("{{{min},}}"),
+            (true, false) => format!("{{{min},{max}}}"),
+        })
+    }
+
+    /// Translates a bracket expression after its `[`. Backslash is an
+    /// ordinary character inside brackets in POSIX.
+    fn bracket(&mut self) -> Result<String, String> {
+        let unmatched = || "unmatched [".to_string();
+        let mut class = String::from("[");
+        if self.peek_is("^") {
+            self.pos += 1;
+            class.push('^');
+        }
+        let body_start = self.pos;
+        let mut first = true;
+        loop {
+            let c = self.next().ok_or_else(unmatched)?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = match c {
+                '[' if self.peek_is(":") => {
+                    let name = self.bracket_word(':').ok_or_else(unmatched)?;
+                    if !POSIX_CLASSES.contains(&name.as_str()) {
+                        return Err(format!("invalid character class '{name}'"));
+                    }
+                    class.push_str(&format!("[:{name}:]"));
+                    continue;
+                }
+                '[' if self.peek_is("=") || self.peek_is(".") => {
+                    let delim = self.chars[self.pos];
+                    let word = self.bracket_word(delim).ok_or_else(unmatched)?;
+                    let mut it = word.chars();
+                    match (it.next(), it.next()) {
+                        (Some(c), None) => c,
+                        _ => return Err(format!("unsupported collating element '{word}'")),
+                    }
+                }
+                c => c,
+            };
+            if self.peek_is("-") && !self.peek_is("-]") && self.chars.get(self.pos + 1).is_some() {
+                self.pos += 1;
+                let hi = self.next().ok_or_else(unmatched)?;
+                if hi < lo {
+                    return Err("invalid range end".to_string());
+                }
+                push_class_char(&mut class, lo);
+                class.push('-');
+                push_class_char(&mut class, hi);
+            } else {
+                push_class_char(&mut class, lo);
+            }
+        }
+        let body: String = self.chars[body_start..self.pos - 1].iter().collect();
+        if body.len() > 2 && body.starts_with(':') && body.ends_with(':') {
+            return Err(format!(
+                "character class syntax is [[{body}]], not [{body}]"
+            ));
+        }
+        class.push(']');
+        Ok(class)
+    }
+
+    /// Reads the name in `[:name:]`, `[=c=]` or `[.c.]` after the `[`.
+    fn bracket_word(&mut self, delim: char) -> Option<String> {
+        self.pos += 1;
+        let from = self.pos;
+        while self.pos + 1 < self.chars.len() {
+            if self.chars[self.pos] == delim && self.chars[self.pos + 1] == ']' {
+                let word = self.chars[from..self.pos].iter().collect();
+                self.pos += 2;
+                return Some(word);
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+fn push_class_char(class: &mut String, c: char) {
+    if "\\[]^-&~".contains(c) {
+        class.push('\\');
+    }
+    class.push(c);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::Regex;
+
+    fn bre(p: &str) -> String {
+        translate(p, Syntax::Basic, false).unwrap()
+    }
+
+    fn ere(p: &str) -> String {
+        translate(p, Syntax::Extended, false).unwrap()
+    }
+
+    #[test]
+    fn basic_operators_are_escaped() {
+        assert_eq!(bre(r"\(ab\)\{2\}"), "(ab){2}");
+        assert_eq!(bre(r"a\|b"), "a|b");
+        assert_eq!(bre(r"a\+b\?"), "a+b?");
+        assert_eq!(bre("a|b+(c)?{1}"), r"a\|b\+\(c\)\?\{1\}");
+    }
+
+    #[test]
+    fn extended_operators() {
+        assert_eq!(ere("(ab){2,3}|c+d?"), "(ab){2,3}|c+d?");
+        assert_eq!(ere(r"\(a\)\|b"), r"\(a\)\|b");
+        assert_eq!(ere("a{,3}"), "a{0,3}");
+        assert_eq!(ere("a{x"), r"a\{x");
+        assert_eq!(ere("{1}"), r"\{1\}");
+    }
+
+    #[test]
+    fn star_and_anchors_in_context() {
+        assert_eq!(bre("*a"), r"\*a");
+        assert_eq!(bre(r"\(*a\)"), r"(\*a)");
+        assert_eq!(bre("^*a"), r"^\*a");
+        assert_eq!(bre("a^b$c"), r"a\^b\$c");
+        assert_eq!(bre(r"^a$"), "^a$");
+        assert_eq!(bre(r"\(^a$\)"), "(^a$)");
+        assert_eq!(ere("a^b$"), "a^b$");
+    }
+
+    #[test]
+    fn stacked_quantifiers_are_grouped() {
+        assert_eq!(bre("a**"), "(?:a*)*");
+        assert_eq!(ere("a+?"), "(?:a+)?");
+        assert_eq!(ere("(ab)*{2}"), "(?:(ab)*){2}");
+    }
+
+    #[test]
+    fn named_groups() {
+        assert_eq!(ere("(?<v>[0-9]+)"), "(?P<v>[0-9]+)");
+        assert_eq!(bre(r"\(?P<k>a\)"), "(?P<k>a)");
+        assert_eq!(ere("(?<>a)"), r"(\?<>a)");
+    }
+
+    #[test]
+    fn inline_flags_and_newlines() {
+        assert_eq!(bre("(?s)a.b"), "(?s)a.b");
+        assert_eq!(bre("(?s)*"), r"(?s)\*");
+        assert_eq!(ere("x(?i:ab)+"), "x(?i:ab)+");
+        assert_eq!(bre("(?z)"), r"\(\?z\)");
+        assert_eq!(bre(r"a\nb"), "anb");
+        assert_eq!(
+            translate(r"a\r\nb", Syntax::Basic, true).unwrap(),
+            r"a\r\nb"
+        );
+    }
+
+    #[test]
+    fn gnu_escapes() {
+        assert_eq!(bre(r"\<id\>"), r"\<id\>");
+        assert_eq!(bre(r"\w\+\s"), r"\w+\s");
+        assert_eq!(bre(r"\`a\'"), r"\Aa\z");
+        assert_eq!(bre(r"a\.b"), r"a\.b");
+    }
+
+    #[test]
+    fn brackets() {
+        assert_eq!(bre("[[:alpha:]_]"), "[[:alpha:]_]");
+        assert_eq!(bre("[^[:digit:]]"), "[^[:digit:]]");
+        assert_eq!(bre(r"[\]"), r"[\\]");
+        assert_eq!(bre("[]a]"), r"[\]a]");
+        assert_eq!(bre("[a-]"), r"[a\-]");
+        assert_eq!(bre("[a-z0-9]"), "[a-z0-9]");
+        assert_eq!(bre("[[=e=][.-.]]"), r"[e\-]");
+        assert_eq!(bre("[&~]"), r"[\&\~]");
+    }
+
+    #[test]
+    fn errors() {
+        let err = |p: &str| translate(p, Syntax::Basic, false).unwrap_err();
+        assert!(err(r"\(a\)\1").contains("back-references"));
+        assert!(err("[[:foo:]]").contains("invalid character class"));
+        assert!(err("[:space:]").contains("[[:space:]]"));
+        assert!(err("[abc").contains("unmatched ["));
+        assert!(err(r"\(a").contains("unmatched"));
+        assert!(err(r"a\{x\}").contains("\\{"));
+        assert!(err("[z-a]").contains("range"));
+        assert!(err("a\\").contains("trailing backslash"));
+        assert!(translate("(a", Syntax::Extended, false).is_err());
+    }
+
+    #[test]
+    fn required_literals() {
+        let lit = |p: &str, syntax| required_literal(p, syntax);
+        assert_eq!(lit("error: ", Syntax::Basic).as_deref(), Some("error: "));
+        assert_eq!(
+            lit("^foo.*barbaz$", Syntax::Basic).as_deref(),
+            Some("barbaz")
+        );
+        // A quantified character may be missing or repeated.
+        assert_eq!(lit("hello*", Syntax::Basic).as_deref(), Some("hell"));
+        assert_eq!(lit("ab?cd", Syntax::Extended).as_deref(), Some("cd"));
+        assert_eq!(lit(r"x\{2\}yz", Syntax::Basic).as_deref(), Some("yz"));
+        // Nothing inside groups; an alternation at the top has no literal.
+        assert_eq!(lit(r"\(abc\)de", Syntax::Basic).as_deref(), Some("de"));
+        assert_eq!(lit("(abc)", Syntax::Extended), None);
+        assert_eq!(lit("foo|bar", Syntax::Extended), None);
+        assert_eq!(lit("foo|bar", Syntax::Basic).as_deref(), Some("foo|bar"));
+        assert_eq!(
+            lit("(foo|bar)baz", Syntax::Extended).as_deref(),
+            Some("baz")
+        );
+        assert_eq!(lit(r"\bid\b", Syntax::Basic).as_deref(), Some("id"));
+        assert_eq!(lit("[0-9]+ms", Syntax::Extended).as_deref(), Some("ms"));
+        assert_eq!(lit("(?i)abc", Syntax::Basic), None);
+        assert_eq!(lit(".*", Syntax::Basic), None);
+        assert_eq!(lit("[oops", Syntax::Basic), None);
+    }
+
+    #[test]
+    fn translated_patterns_compile() {
+        for (p, syntax) in [
+            (r"\(ab\)*\{2,\}", Syntax::Basic),
+            ("[[:upper:]][[:lower:]]+|[0-9]{2}", Syntax::Extended),
+            ("a**b", Syntax::Basic),
+        ] {
+            let t = translate(p, syntax, false).unwrap();
+            assert!(Regex::new(&t).is_ok(), "{p} -> {t}");
+        }
+    }
+}
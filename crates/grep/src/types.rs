@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+/// Built-in file types for `-t`/`-T`, by name, with the globs that select
+/// them. Matched against base names, like `--include`.
+const BUILTIN: &[(&str, &[&str])] = &[
+    ("bat", &["*.bat", "*.cmd"]),
+    ("c", &["*.c", "*.h"]),
+    ("cargo", &["Cargo.toml", "Cargo.lock"]),
+    ("cmake", &["CMakeLists.txt", "*.cmake"]),
+    ("config", &["*.config", "*.ini", "*.cfg", "*.conf"]),
+    (
+        "cpp",
+        &[
+            "*.cpp", "*.cc", "*.cxx", "*.c++", "*.hpp", "*.hh", "*.hxx", "*.h", "*.inl",
+        ],
+    ),
+    ("cs", &["*.cs", "*.csx"]),
+    ("css", &["*.css", "*.scss", "*.sass", "*.less"]),
+    ("csv", &["*.csv", "*.tsv"]),
+    ("fsharp", &["*.fs", "*.fsi", "*.fsx"]),
+    ("go", &["*.go"]),
+    (
+        "html",
+        &["*.html", "*.htm", "*.xhtml", "*.cshtml", "*.razor"],
+    ),
+    ("java", &["*.java"]),
+    ("js", &["*.js", "*.mjs", "*.cjs", "*.jsx"]),
+    ("json", &["*.json", "*.jsonc", "*.json5"]),
+    ("kotlin", &["*.kt", "*.kts"]),
+    ("log", &["*.log"]),
+    ("lua", &["*.lua"]),
+    (
+        "make",
+        &["Makefile", "makefile", "GNUmakefile", "*.mk", "*.mak"],
+    ),
+    ("markdown", &["*.md", "*.markdown", "*.mdx"]),
+    (
+        "msbuild",
+        &[
+            "*.csproj",
+            "*.vbproj",
+            "*.fsproj",
+            "*.vcxproj",
+            "*.proj",
+            "*.props",
+            "*.targets",
+        ],
+    ),
+    ("php", &["*.php"]),
+    ("ps1", &["*.ps1", "*.psm1", "*.psd1"]),
+    ("py", &["*.py", "*.pyi", "*.pyw"]),
+    ("rb", &["*.rb", "Gemfile", "Rakefile"]),
+    ("rust", &["*.rs"]),
+    ("sh", &["*.sh", "*.bash", "*.zsh"]),
+    ("sln", &["*.sln", "*.slnx"]),
+    ("sql", &["*.sql"]),
+    ("swift", &["*.swift"]),
+    ("toml", &["*.toml"]),
+    ("ts", &["*.ts", "*.tsx", "*.mts", "*.cts"]),
+    ("txt", &["*.txt"]),
+    ("vb", &["*.vb", "*.vbs"]),
+    (
+        "xml",
+        &[
+            "*.xml",
+            "*.xsd",
+            "*.xsl",
+            "*.xslt",
+            "*.xaml",
+            "*.resx",
+            "*.manifest",
+        ],
+    ),
+    ("yaml", &["*.yaml", "*.yml"]),
+];
+
+/// The file types known to `-t`, `-T` and `--type-list`: the built-in
+/// table plus any `--type-add` definitions.
+pub struct Types {
+    globs: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Types {
+    fn default() -> Self {
+        let globs = BUILTIN
+            .iter()
+            .map(|(name, globs)| {
+                (
+                    name.to_string(),
+                    globs.iter().map(|g| g.to_string()).collect(),
+                )
+            })
+            .collect();
+        Types { globs }
+    }
+}
+
+impl Types {
+    /// `--type-add NAME:GLOB`: adds GLOB to type NAME, defining the type if
+    /// it is new.
+    pub fn add(&mut self, spec: &str) -> Result<(), String> {
+        let (name, glob) = spec
+            .split_once(':')
+            .filter(|(name, glob)| !name.is_empty() && !glob.is_empty())
+            .ok_or_else(|| {
+                format!("invalid argument '{spec}' for --type-add (expected NAME:GLOB)")
+            })?;
+        self.globs
+            .entry(name.to_string())
+            .or_default()
+            .push(glob.to_string());
+        Ok(())
+    }
+
+    pub fn globs(&self, name: &str) -> Result<&[String], String> {
+        self.globs
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| format!("unknown file type '{name}' (see --type-list)"))
+    }
+
+    /// `--type-list` output: one `name: glob, glob` line per type, by name.
+    pub fn list(&self) -> String {
+        self.globs
+            .iter()
+            .map(|(name, globs)| format!("{name}: {}\n", globs.join(", ")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_table_is_sorted_and_unique() {
+        assert!(BUILTIN.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(Types::default().globs.len(), BUILTIN.len());
+    }
+
+    #[test]
+    fn type_add_extends_or_defines() {
+        let mut types = Types::default();
+        types.add("rust:*.rs.in").unwrap();
+        types.add("proto:*.proto").unwrap();
+        assert_eq!(types.globs("rust").unwrap(), ["*.rs", "*.rs.in"]);
+        assert_eq!(types.globs("proto").unwrap(), ["*.proto"]);
+        assert!(types.list().contains("proto: *.proto\n"));
+        assert!(types.add("proto").is_err());
+        assert!(types.add(":*.x").is_err());
+        assert!(types.globs("nope").is_err());
+    }
+}
@@ -0,0 +1,551 @@
+use crate::glob::Glob;
+use crate::ignore::Ignores;
+use crate::stats::STATS;
+use crate::TOOL;
+use common::error;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// `--include`, `--exclude` and `--exclude-dir` rules, and the file types
+/// selected with `-t` and `-T`.
+#[derive(Default)]
+pub struct Filters {
+    // In command-line order; `true` for `--include`.
+    files: Vec<(Glob, bool)>,
+    dirs: Vec<Glob>,
+    types: Vec<Glob>,
+    not_types: Vec<Glob>,
+}
+
+impl Filters {
+    pub fn include(&mut self, pattern: &str) {
+        self.files.push((Glob::new(pattern), true));
+    }
+
+    pub fn exclude(&mut self, pattern: &str) {
+        self.files.push((Glob::new(pattern), false));
+    }
+
+    pub fn exclude_dir(&mut self, pattern: &str) {
+        self.dirs.push(Glob::new(pattern));
+    }
+
+    /// `-t`: search only files of this type (or of another selected one).
+    pub fn select_type(&mut self, globs: &[String]) {
+        self.types.extend(globs.iter().map(|g| Glob::new(g)));
+    }
+
+    /// `-T`: skip files of this type.
+    pub fn negate_type(&mut self, globs: &[String]) {
+        self.not_types.extend(globs.iter().map(|g| Glob::new(g)));
+    }
+
+    /// Type rules apply to files found while walking a directory; a file
+    /// named on the command line is searched whatever its type.
+    fn type_allowed(&self, name: &str) -> bool {
+        (self.types.is_empty() || self.types.iter().any(|g| g.is_match(name)))
+            && !self.not_types.iter().any(|g| g.is_match(name))
+    }
+
+    /// GNU grep's rule: the last matching `--include`/`--exclude` wins, and
+    /// a file matching none is searched unless the first rule is an include.
+    fn file_allowed(&self, names: &[&str]) -> bool {
+        let mut allowed = !matches!(self.files.first(), Some((_, true)));
+        for (glob, include) in &self.files {
+            if names.iter().any(|n| glob.is_match(n)) {
+                allowed = *include;
+            }
+        }
+        allowed
+    }
+
+    fn dir_allowed(&self, names: &[&str]) -> bool {
+        !self
+            .dirs
+            .iter()
+            .any(|glob| names.iter().any(|n| glob.is_match(n)))
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || (cfg!(windows) && c == '\\')
+}
+
+/// A command-line name and each trailing part of it that starts after a
+/// separator, so `--exclude=*.log` and `--exclude=logs/a.log` both match
+/// `build/logs/a.log`.
+fn name_suffixes(name: &str) -> Vec<&str> {
+    let name = name.trim_end_matches(is_separator);
+    let mut suffixes = vec![name];
+    suffixes.extend(
+        name.match_indices(is_separator)
+            .map(|(i, sep)| &name[i + sep.len()..])
+            .filter(|s| !s.is_empty() && !s.starts_with(is_separator)),
+    );
+    suffixes
+}
+
+/// How command-line directories are expanded into files.
+#[derive(Default)]
+pub struct Options {
+    pub recursive: bool,
+    // `-R`: follow symbolic links (and Windows junctions) found while
+    // walking. Links named on the command line are always followed.
+    pub follow_links: bool,
+    // `--max-depth`: 1 searches only the files directly inside a directory.
+    pub max_depth: Option<usize>,
+    pub one_file_system: bool,
+    pub hidden: bool,
+    pub gitignore: bool,
+    pub filters: Filters,
+    // `-s`: don't report unreadable files and directories.
+    pub no_messages: bool,
+}
+
+impl Options {
+    pub fn warn(&self, msg: &str) {
+        if !self.no_messages {
+            error::warn(TOOL, msg);
+        }
+    }
+}
+
+/// Calls `emit` with each file to search, in path order: command-line
+/// operands as given, directory contents sorted by name. Stops early if
+/// `emit` returns false. `-` (stdin) is passed on as it is.
+pub fn for_each_file(paths: &[String], opts: &Options, emit: &mut dyn FnMut(String) -> bool) {
+    for p in paths {
+        let path = Path::new(p);
+        let names = name_suffixes(p);
+        let more = if p == "-" {
+            emit(p.clone())
+        } else if path.is_dir() {
+            if !opts.recursive {
+                opts.warn(&format!("{p}: Is a directory"));
+                true
+            } else if opts.filters.dir_allowed(&names) {
+                Walk::new(path, opts).dir(path, 0, emit)
+            } else {
+                STATS.excluded();
+                true
+            }
+        } else if opts.filters.file_allowed(&names) {
+            emit(p.clone())
+        } else {
+            STATS.excluded();
+            true
+        };
+        if !more {
+            return;
+        }
+    }
+}
+
+/// Identifies a directory however it is reached: the device (volume serial
+/// number on Windows) and inode (file index) it lives at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FileId {
+    device: u64,
+    index: u64,
+}
+
+#[cfg(unix)]
+fn file_id(path: &Path) -> io::Result<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = fs::metadata(path)?;
+    Ok(FileId {
+        device: meta.dev(),
+        index: meta.ino(),
+    })
+}
+
+#[cfg(windows)]
+fn file_id(path: &Path) -> io::Result<FileId> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+
+    // Lets CreateFile open a directory.
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct ByHandleFileInformation {
+        attributes: u32,
+        // Creation, last access and last write FILETIMEs.
+        times: [u32; 6],
+        volume_serial_number: u32,
+        size_high: u32,
+        size_low: u32,
+        links: u32,
+        index_high: u32,
+        index_low: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetFileInformationByHandle(
+            file: std::os::windows::io::RawHandle,
+            info: *mut ByHandleFileInformation,
+        ) -> i32;
+    }
+
+    let file = fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)?;
+    let mut info = ByHandleFileInformation::default();
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(FileId {
+        device: u64::from(info.volume_serial_number),
+        index: u64::from(info.index_high) << 32 | u64::from(info.index_low),
+    })
+}
+
+/// State for walking one command-line directory.
+struct Walk<'a> {
+    opts: &'a Options,
+    ignores: Ignores,
+    // Path of the current directory relative to the repository root (or the
+    // walk's root outside a repository), for matching ignore rules.
+    components: Vec<String>,
+    in_repo: bool,
+    // The directories being walked, root first, to detect link loops.
+    ancestors: Vec<FileId>,
+}
+
+impl<'a> Walk<'a> {
+    fn new(root: &Path, opts: &'a Options) -> Self {
+        let (ignores, components, in_repo) = if opts.gitignore {
+            Ignores::for_root(root)
+        } else {
+            Default::default()
+        };
+        Walk {
+            opts,
+            ignores,
+            components,
+            in_repo,
+            ancestors: file_id(root).into_iter().collect(),
+        }
+    }
+
+    /// Whether to descend into `path`, a directory: not if it is one of the
+    /// directories being walked (a link loop) or, with
+    /// `--one-file-system`, on another device than the root.
+    fn enter_allowed(&mut self, path: &Path) -> bool {
+        let id = match file_id(path) {
+            Ok(id) => id,
+            Err(e) => {
+                STATS.unreadable();
+                self.opts.warn(&format!("{}: {e}", path.display()));
+                return false;
+            }
+        };
+        if self.ancestors.contains(&id) {
+            STATS.excluded();
+            self.opts.warn(&format!(
+                "{}: warning: recursive directory loop",
+                path.display()
+            ));
+            return false;
+        }
+        if self.opts.one_file_system
+            && self
+                .ancestors
+                .first()
+                .is_some_and(|r| r.device != id.device)
+        {
+            STATS.excluded();
+            return false;
+        }
+        self.ancestors.push(id);
+        true
+    }
+
+    /// Walks `dir`, `depth` levels below the root, returning false if
+    /// `emit` asked to stop.
+    fn dir(&mut self, dir: &Path, depth: usize, emit: &mut dyn FnMut(String) -> bool) -> bool {
+        if self.opts.max_depth.is_some_and(|max| depth >= max) {
+            STATS.excluded();
+            return true;
+        }
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(e) => {
+                STATS.unreadable();
+                self.opts.warn(&format!("{}: {e}", dir.display()));
+                return true;
+            }
+        };
+        let pushed = if self.opts.gitignore {
+            self.ignores.enter(dir, self.components.len(), self.in_repo)
+        } else {
+            0
+        };
+        let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+        entries.sort_by_key(|e| e.file_name());
+        let mut more = true;
+        for entry in entries {
+            if !more {
+                break;
+            }
+            let name = entry.file_name();
+            let name_str = name.to_string_lossy();
+            if name_str.starts_with('.') && !self.opts.hidden {
+                STATS.excluded();
+                continue;
+            }
+            let path = entry.path();
+            let is_link = entry.file_type().is_ok_and(|t| t.is_symlink());
+            if is_link && !self.opts.follow_links {
+                STATS.excluded();
+                continue;
+            }
+            let is_dir = path.is_dir();
+            if self.opts.gitignore {
+                if is_dir && name_str == ".git" {
+                    continue;
+                }
+                self.components.push(name_str.to_string());
+                let ignored = self.ignores.is_ignored(&self.components, is_dir);
+                self.components.pop();
+                if ignored {
+                    STATS.excluded();
+                    continue;
+                }
+            }
+            if is_dir {
+                if !self.opts.filters.dir_allowed(&[&name_str]) {
+                    STATS.excluded();
+                } else if self.enter_allowed(&path) {
+                    self.components.push(name_str.to_string());
+                    more = self.dir(&path, depth + 1, emit);
+                    self.components.pop();
+                    self.ancestors.pop();
+                }
+            } else if self.opts.filters.file_allowed(&[&name_str])
+                && self.opts.filters.type_allowed(&name_str)
+            {
+                more = emit(path.to_string_lossy().into_owned());
+            } else {
+                STATS.excluded();
+            }
+        }
+        self.ignores.leave(pushed);
+        more
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_files(paths: &[String], opts: &Options) -> Vec<String> {
+        let mut files = Vec::new();
+        for_each_file(paths, opts, &mut |f| {
+            files.push(f);
+            true
+        });
+        files
+    }
+
+    #[test]
+    fn suffixes_of_command_line_names() {
+        assert_eq!(
+            name_suffixes("build/logs/a.log"),
+            vec!["build/logs/a.log", "logs/a.log", "a.log"]
+        );
+        assert_eq!(name_suffixes("target/"), vec!["target"]);
+        assert_eq!(name_suffixes("a//b"), vec!["a//b", "b"]);
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let mut f = Filters::default();
+        assert!(f.file_allowed(&["a.rs"]));
+
+        f.exclude("*.rs");
+        assert!(!f.file_allowed(&["a.rs"]));
+        assert!(f.file_allowed(&["a.txt"]));
+
+        f.include("main.*");
+        assert!(f.file_allowed(&["main.rs"]));
+        assert!(!f.file_allowed(&["lib.rs"]));
+    }
+
+    #[test]
+    fn leading_include_excludes_the_rest() {
+        let mut f = Filters::default();
+        f.include("*.rs");
+        f.include("*.toml");
+        assert!(f.file_allowed(&["a.rs"]));
+        assert!(f.file_allowed(&["Cargo.toml"]));
+        assert!(!f.file_allowed(&["README.md"]));
+    }
+
+    #[test]
+    fn selected_and_negated_types() {
+        let mut f = Filters::default();
+        assert!(f.type_allowed("a.rs"));
+
+        f.select_type(&["*.rs".to_string()]);
+        f.select_type(&["Cargo.toml".to_string()]);
+        assert!(f.type_allowed("a.rs"));
+        assert!(f.type_allowed("Cargo.toml"));
+        assert!(!f.type_allowed("b.toml"));
+
+        f.negate_type(&["*.rs".to_string()]);
+        assert!(!f.type_allowed("a.rs"));
+    }
+
+    #[test]
+    fn excluded_dirs() {
+        let mut f = Filters::default();
+        f.exclude_dir("target");
+        f.exclude_dir("node_*");
+        assert!(!f.dir_allowed(&["target"]));
+        assert!(!f.dir_allowed(&["node_modules"]));
+        assert!(f.dir_allowed(&["src"]));
+    }
+
+    #[test]
+    fn walk_prunes_excluded_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "x").unwrap();
+        fs::write(dir.path().join("src/notes.md"), "x").unwrap();
+        fs::write(dir.path().join("target/debug/out.rs"), "x").unwrap();
+
+        let mut opts = Options {
+            recursive: true,
+            ..Options::default()
+        };
+        opts.filters.exclude_dir("target");
+        opts.filters.include("*.rs");
+        let root = dir.path().to_string_lossy().into_owned();
+        let files = collect_files(&[root], &opts);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("main.rs"));
+    }
+
+    fn walk_names(root: &Path, opts: &Options) -> Vec<String> {
+        let root_str = root.to_string_lossy().into_owned();
+        let mut names: Vec<String> = collect_files(std::slice::from_ref(&root_str), opts)
+            .iter()
+            .map(|f| f[root_str.len() + 1..].replace('\\', "/"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn walk_stops_at_max_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        for file in ["top.txt", "a/mid.txt", "a/b/deep.txt"] {
+            fs::write(dir.path().join(file), "x").unwrap();
+        }
+        let mut opts = Options {
+            recursive: true,
+            ..Options::default()
+        };
+        assert_eq!(walk_names(dir.path(), &opts).len(), 3);
+        opts.max_depth = Some(2);
+        assert_eq!(walk_names(dir.path(), &opts), vec!["a/mid.txt", "top.txt"]);
+        opts.max_depth = Some(1);
+        assert_eq!(walk_names(dir.path(), &opts), vec!["top.txt"]);
+        opts.max_depth = Some(0);
+        assert!(walk_names(dir.path(), &opts).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_followed_only_with_follow_links() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("other")).unwrap();
+        fs::write(root.join("src/main.rs"), "x").unwrap();
+        fs::write(root.join("other/lib.rs"), "x").unwrap();
+        symlink(root.join("other"), root.join("src/linked")).unwrap();
+        symlink(root.join("other/lib.rs"), root.join("src/lib.rs")).unwrap();
+        // Loops back to the directory being walked.
+        symlink(root.join("src"), root.join("src/self")).unwrap();
+        let src = root.join("src");
+
+        let mut opts = Options {
+            recursive: true,
+            no_messages: true,
+            ..Options::default()
+        };
+        assert_eq!(walk_names(&src, &opts), vec!["main.rs"]);
+
+        opts.follow_links = true;
+        assert_eq!(
+            walk_names(&src, &opts),
+            vec!["lib.rs", "linked/lib.rs", "main.rs"]
+        );
+    }
+
+    #[test]
+    fn walk_respects_gitignore_and_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for sub in [".git/info", "src/gen", "out", ".config"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        for (file, text) in [
+            (".gitignore", "out/\n*.tmp\n"),
+            (".git/info/exclude", "secret.txt\n"),
+            ("src/.gitignore", "gen/\n!keep.tmp\n"),
+            (".ignore", "notes.md\n"),
+            ("src/main.rs", "x"),
+            ("src/a.tmp", "x"),
+            ("src/keep.tmp", "x"),
+            ("src/gen/g.rs", "x"),
+            ("out/o.rs", "x"),
+            ("secret.txt", "x"),
+            ("notes.md", "x"),
+            (".config/c.toml", "x"),
+        ] {
+            fs::write(root.join(file), text).unwrap();
+        }
+
+        let mut opts = Options {
+            recursive: true,
+            gitignore: true,
+            ..Options::default()
+        };
+        assert_eq!(walk_names(root, &opts), vec!["src/keep.tmp", "src/main.rs"]);
+
+        opts.hidden = true;
+        assert_eq!(
+            walk_names(root, &opts),
+            vec![
+                ".config/c.toml",
+                ".gitignore",
+                ".ignore",
+                "src/.gitignore",
+                "src/keep.tmp",
+                "src/main.rs"
+            ]
+        );
+
+        // Starting below the repository root still applies parent rules.
+        opts.hidden = false;
+        assert_eq!(
+            walk_names(&root.join("src"), &opts),
+            vec!["keep.tmp", "main.rs"]
+        );
+
+        opts.gitignore = false;
+        assert_eq!(walk_names(root, &opts).len(), 7);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::inflate::inflate;
+use std::path::Path;
+
+/// Extensions of the archives `--search-zip` opens: zip itself, and the
+/// formats that are zip files under another name.
+const ARCHIVES: &[&str] = &["zip", "jar", "war", "nupkg", "docx", "xlsx", "pptx"];
+/// Office Open XML documents, whose XML parts are searched as text.
+const OFFICE: &[&str] = &["docx", "xlsx", "pptx"];
+
+fn has_extension(path: &str, extensions: &[&str]) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.iter().any(|x| e.eq_ignore_ascii_case(x)))
+}
+
+pub fn is_archive(path: &str) -> bool {
+    has_extension(path, ARCHIVES)
+}
+
+pub fn is_office(path: &str) -> bool {
+    has_extension(path, OFFICE)
+}
+
+/// One file in an archive's central directory.
+pub struct Entry {
+    pub name: String,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    header_offset: usize,
+}
+
+impl Entry {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+
+/// `len` bytes of `bytes` from `at`, if they are all there. Offsets and
+/// sizes come from the archive, so the sum is checked.
+fn slice_at(bytes: &[u8], at: usize, len: usize) -> Option<&[u8]> {
+    bytes.get(at..at.checked_add(len)?)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(slice_at(bytes, at, 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(slice_at(bytes, at, 4)?.try_into().ok()?))
+}
+
+/// The entries of the zip archive `archive`, in central directory order.
+pub fn entries(archive: &[u8]) -> Result<Vec<Entry>, String> {
+    // The end of central directory record is the last 22 bytes, unless the
+    // archive has a comment after it, of at most 64 KiB.
+    let last = archive.len().checked_sub(22).ok_or("not a zip archive")?;
+    let end = (last.saturating_sub(0xFFFF)..=last)
+        .rev()
+        .find(|&at| u32_at(archive, at) == Some(END_OF_DIRECTORY))
+        .ok_or("not a zip archive")?;
+    let count = u16_at(archive, end + 10).unwrap_or_default();
+    let offset = u32_at(archive, end + 16).unwrap_or_default();
+    if count == 0xFFFF || offset == 0xFFFF_FFFF {
+        return Err("zip64 archives are not supported".to_string());
+    }
+
+    let truncated = || "truncated central directory".to_string();
+    let mut at = offset as usize;
+    let mut entries = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        if u32_at(archive, at) != Some(CENTRAL_HEADER) {
+            return Err(truncated());
+        }
+        // The signature check puts `at` inside the archive, so adding fixed
+        // offsets and 16-bit lengths to it can't overflow.
+        let field16 = |offset| u16_at(archive, at + offset).ok_or_else(truncated);
+        let field32 = |offset| u32_at(archive, at + offset).ok_or_else(truncated);
+        let name_len = usize::from(field16(28)?);
+        let extra_len = usize::from(field16(30)?);
+        let comment_len = usize::from(field16(32)?);
+        let name = slice_at(archive, at + 46, name_len).ok_or_else(truncated)?;
+        entries.push(Entry {
+            // Names not flagged UTF-8 are in code page 437, which agrees
+            // with UTF-8 on the ASCII names nearly all archives use.
+            name: String::from_utf8_lossy(name).into_owned(),
+            flags: field16(8)?,
+            method: field16(10)?,
+            crc: field32(16)?,
+            compressed_size: field32(20)? as usize,
+            size: field32(24)? as usize,
+            header_offset: field32(42)? as usize,
+        });
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// The contents of `entry`, decompressed and checked against its CRC.
+pub fn read(archive: &[u8], entry: &Entry) -> Result<Vec<u8>, String> {
+    if entry.flags & 1 != 0 {
+        return Err("encrypted entries are not supported".to_string());
+    }
+    let at = entry.header_offset;
+    if u32_at(archive, at) != Some(LOCAL_HEADER) {
+        return Err("invalid local header".to_string());
+    }
+    // The local header's own name and extra field lengths may differ from
+    // the central directory's; its sizes may be zero, so those are not used.
+    // As in `entries`, only the 32-bit size needs a checked sum.
+    let name_len = usize::from(u16_at(archive, at + 26).unwrap_or_default());
+    let extra_len = usize::from(u16_at(archive, at + 28).unwrap_or_default());
+    let data = slice_at(
+        archive,
+        at + 30 + name_len + extra_len,
+        entry.compressed_size,
+    )
+    .ok_or("truncated entry")?;
+    let contents = match entry.method {
+        0 => data.to_vec(),
+        8 => inflate(data, entry.size)?,
+        method => return Err(format!("unsupported compression method {method}")),
+    };
+    if contents.len() != entry.size || crc32(&contents) != entry.crc {
+        return Err("corrupt entry (size or CRC mismatch)".to_string());
+    }
+    Ok(contents)
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |c, &b| {
+        CRC_TABLE[usize::from(c as u8 ^ b)] ^ (c >> 8)
+    })
+}
+
+/// Parts of an Office document that describe the package rather than hold
+/// its content.
+pub fn is_office_metadata(name: &str) -> bool {
+    name == "[Content_Types].xml" || name.ends_with(".rels")
+}
+
+/// The text of an Office XML part: tags are dropped and entities decoded,
+/// with a line per paragraph, spreadsheet row or shared string, and a tab
+/// between cells. Line breaks between tags only lay out the XML, so they
+/// are dropped.
+pub fn office_text(xml: &str) -> String {
+    let mut text = String::with_capacity(xml.len() / 2);
+    let mut rest = xml;
+    while let Some(lt) = rest.find('<') {
+        let between = &rest[..lt];
+        if !(between.contains('\n') && between.trim().is_empty()) {
+            unescape(between, &mut text);
+        }
+        let Some(gt) = rest[lt..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[lt + 1..lt + gt];
+        let name = tag
+            .split(|c: char| c.is_ascii_whitespace())
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        match name {
+            "/w:p" | "/a:p" | "/si" | "/row" | "w:br" | "w:cr" => text.push('\n'),
+            "/c" | "w:tab" => text.push('\t'),
+            _ => {}
+        }
+        rest = &rest[lt + gt + 1..];
+    }
+    unescape(rest, &mut text);
+    text
+}
+
+/// Appends `s` to `text` with XML's character and entity references
+/// decoded. Unknown references are kept as they are.
+fn unescape(mut s: &str, text: &mut String) {
+    while let Some(amp) = s.find('&') {
+        text.push_str(&s[..amp]);
+        s = &s[amp..];
+        let decoded = s.find(';').and_then(|semi| {
+            let c = match &s[1..semi] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                r => {
+                    let code = match r.strip_prefix("#x") {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => r.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, semi + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                text.push(c);
+                s = &s[len..];
+            }
+            None => {
+                text.push('&');
+                s = &s[1..];
+            }
+        }
+    }
+    text.push_str(s);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A zip archive holding `files`, stored without compression.
+    fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data) in files {
+            let offset = out.len() as u32;
+            let sizes = [crc32(data), data.len() as u32, data.len() as u32];
+            out.extend(LOCAL_HEADER.to_le_bytes());
+            out.extend([20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            out.extend(sizes.iter().flat_map(|n| n.to_le_bytes()));
+            out.extend((name.len() as u16).to_le_bytes());
+            out.extend([0, 0]);
+            out.extend(name.as_bytes());
+            out.extend(*data);
+
+            central.extend(CENTRAL_HEADER.to_le_bytes());
+            central.extend([20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            central.extend(sizes.iter().flat_map(|n| n.to_le_bytes()));
+            central.extend((name.len() as u16).to_le_bytes());
+            central.extend([0; 12]);
+            central.extend(offset.to_le_bytes());
+            central.extend(name.as_bytes());
+        }
+        let count = (files.len() as u16).to_le_bytes();
+        let directory = [central.len() as u32, out.len() as u32];
+        out.extend(central);
+        out.extend(END_OF_DIRECTORY.to_le_bytes());
+        out.extend([0, 0, 0, 0]);
+        out.extend(count);
+        out.extend(count);
+        out.extend(directory.iter().flat_map(|n| n.to_le_bytes()));
+        out.extend([0, 0]);
+        out
+    }
+
+    #[test]
+    fn reads_entries() {
+        let zip = archive(&[("dir/", b""), ("dir/a.txt", b"hello\n")]);
+        let mut entries = entries(&zip).unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["dir/", "dir/a.txt"]);
+        assert!(entries[0].is_dir() && !entries[1].is_dir());
+        assert_eq!(read(&zip, &entries[1]).unwrap(), b"hello\n");
+
+        // A changed byte fails the CRC check.
+        let mut corrupt = zip.clone();
+        let at = corrupt.windows(6).position(|w| w == b"hello\n").unwrap();
+        corrupt[at] = b'j';
+        assert!(read(&corrupt, &entries[1]).is_err());
+
+        // A size near the end of the address space is truncation, not an
+        // overflow.
+        let mut huge = entries.pop().unwrap();
+        huge.compressed_size = usize::MAX - 8;
+        assert_eq!(read(&zip, &huge).unwrap_err(), "truncated entry");
+
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert!(super::entries(b"not a zip archive at all").is_err());
+        assert!(super::entries(&zip[..zip.len() - 30]).is_err());
+    }
+
+    #[test]
+    fn archive_extensions() {
+        assert!(is_archive("lib/app.JAR") && is_archive("spec.docx"));
+        assert!(!is_archive("notes.txt") && !is_archive("zip"));
+        assert!(is_office("book.xlsx") && !is_office("pkg.nupkg"));
+        assert!(is_office_metadata("[Content_Types].xml"));
+        assert!(is_office_metadata("word/_rels/document.xml.rels"));
+    }
+
+    #[test]
+    fn office_text_keeps_paragraphs_and_cells() {
+        let docx = "<?xml version=\"1.0\"?>\r\n<w:document><w:body>\
+                    <w:p><w:r><w:t>Fish &amp; chips</w:t></w:r></w:p>\
+                    <w:p><w:r><w:t xml:space=\"preserve\">split </w:t></w:r>\
+                    <w:r><w:t>run&#x2014;&#33;</w:t><w:tab/><w:t>&bogus; &amp</w:t></w:r></w:p>\
+                    </w:body></w:document>";
+        assert_eq!(
+            office_text(docx),
+            "Fish & chips\nsplit run\u{2014}!\t&bogus; &amp\n"
+        );
+        let sheet = "<sheetData><row r=\"1\"><c r=\"A1\"><v>1</v></c>\
+                     <c r=\"B1\"><v>2.5</v></c></row><row r=\"2\"/></sheetData>";
+        assert_eq!(office_text(sheet), "1\t2.5\t\n");
+    }
+}
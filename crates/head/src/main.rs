@@ -1,6 +1,8 @@
 use common::cli;
 use common::encoding;
 use common::error;
+use common::filelist::{self, Delimiter};
+use common::header::{self, HeaderArg, HeaderOpts};
 use common::lexopt;
 use common::lexopt::prelude::*;
 use std::io::{self, BufRead, Read, Write};
@@ -13,6 +15,13 @@ enum Mode {
     Bytes(usize),
 }
 
+struct Opts {
+    mode: Mode,
+    headers: HeaderOpts,
+    files: Vec<String>,
+    from_list: bool,
+}
+
 fn expand_dash_n(args: Vec<String>) -> Vec<String> {
     let mut out = Vec::new();
     for arg in args {
@@ -26,9 +35,11 @@ fn expand_dash_n(args: Vec<String>) -> Vec<String> {
     out
 }
 
-fn parse_args() -> (Mode, Vec<String>) {
+fn parse_args() -> Opts {
     let mut mode = Mode::Lines(10);
+    let mut headers = HeaderOpts::default();
     let mut files = Vec::new();
+    let mut from_list = false;
     let raw_args: Vec<String> = std::env::args().collect();
     let expanded = expand_dash_n(raw_args[1..].to_vec());
     let mut parser = lexopt::Parser::from_args(expanded);
@@ -37,6 +48,10 @@ fn parse_args() -> (Mode, Vec<String>) {
         .next()
         .unwrap_or_else(|e| error::err(TOOL, &e.to_string()))
     {
+        if let Some(header_arg) = HeaderArg::of(&arg) {
+            headers.apply(TOOL, header_arg, &mut parser);
+            continue;
+        }
         match arg {
             Short('n') | Long("lines") => {
                 let val: usize = parser
@@ -54,6 +69,14 @@ fn parse_args() -> (Mode, Vec<String>) {
                     .unwrap_or_else(|e| error::err(TOOL, &e.to_string()));
                 mode = Mode::Bytes(val);
             }
+            Long("files-from") => {
+                files.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Newline));
+                from_list = true;
+            }
+            Long("files0-from") => {
+                files.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Nul));
+                from_list = true;
+            }
             Long("help") => {
                 cli::print_help(TOOL, "output the first part of files");
                 std::process::exit(0);
@@ -72,7 +95,12 @@ fn parse_args() -> (Mode, Vec<String>) {
         }
     }
 
-    (mode, files)
+    Opts {
+        mode,
+        headers,
+        files,
+        from_list,
+    }
 }
 
 fn head_lines(reader: impl BufRead, n: usize, out: &mut impl Write) -> io::Result<()> {
@@ -91,32 +119,28 @@ fn head_bytes(reader: impl Read, n: usize, out: &mut impl Write) -> io::Result<(
 }
 
 fn run() -> io::Result<()> {
-    let (mode, files) = parse_args();
+    let opts = parse_args();
     let stdout = io::stdout();
     let mut out = stdout.lock();
-    let multiple = files.len() > 1;
-
-    if files.is_empty() {
-        let reader = encoding::open_stdin()?;
-        match &mode {
-            Mode::Lines(n) => head_lines(reader, *n, &mut out)?,
-            Mode::Bytes(n) => head_bytes(reader, *n, &mut out)?,
-        }
+    let files = if opts.files.is_empty() && !opts.from_list {
+        vec!["-".to_string()]
     } else {
-        for (i, path) in files.iter().enumerate() {
-            if multiple {
-                if i > 0 {
-                    writeln!(out)?;
-                }
-                writeln!(out, "==> {path} <==")?;
-            }
-            let reader = encoding::open_input(path).unwrap_or_else(|e| {
+        opts.files
+    };
+    let mut header = opts.headers.header();
+    let show_headers = header.enabled(files.len());
+
+    for path in &files {
+        if show_headers {
+            header.write(&mut out, header::label(path))?;
+        }
+        let reader = encoding::open_input_or_stdin((path != "-").then_some(path.as_str()))
+            .unwrap_or_else(|e| {
                 error::err(TOOL, &format!("{path}: {e}"));
             });
-            match &mode {
-                Mode::Lines(n) => head_lines(reader, *n, &mut out)?,
-                Mode::Bytes(n) => head_bytes(reader, *n, &mut out)?,
-            }
+        match &opts.mode {
+            Mode::Lines(n) => head_lines(reader, *n, &mut out)?,
+            Mode::Bytes(n) => head_bytes(reader, *n, &mut out)?,
         }
     }
     Ok(())
@@ -1,15 +1,19 @@
 use common::cli;
 use common::encoding;
 use common::error;
+use common::filelist::{self, Delimiter};
+use common::input::{self, Input, SeekableFile};
 use common::lexopt;
 use common::lexopt::prelude::*;
 use std::io::{self, BufRead, Write};
 
 const TOOL: &str = "tac";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+const CHUNK: usize = 64 * 1024;
 
-fn parse_args() -> Vec<String> {
+fn parse_args() -> (Vec<String>, bool) {
     let mut files = Vec::new();
+    let mut from_list = false;
     let mut parser = lexopt::Parser::from_env();
 
     while let Some(arg) = parser
@@ -17,6 +21,14 @@ fn parse_args() -> Vec<String> {
         .unwrap_or_else(|e| error::err(TOOL, &e.to_string()))
     {
         match arg {
+            Long("files-from") => {
+                files.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Newline));
+                from_list = true;
+            }
+            Long("files0-from") => {
+                files.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Nul));
+                from_list = true;
+            }
             Long("help") => {
                 cli::print_help(TOOL, "concatenate and print files in reverse");
                 std::process::exit(0);
@@ -35,7 +47,7 @@ fn parse_args() -> Vec<String> {
         }
     }
 
-    files
+    (files, from_list)
 }
 
 fn tac(reader: impl BufRead, out: &mut impl Write) -> io::Result<()> {
@@ -50,20 +62,77 @@ fn tac(reader: impl BufRead, out: &mut impl Write) -> io::Result<()> {
     Ok(())
 }
 
+fn write_line(line: &[u8], out: &mut impl Write) -> io::Result<()> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    out.write_all(line)?;
+    out.write_all(b"\n")
+}
+
+/// Writes the line made of `head` and the pieces of it read before, which
+/// `tail` holds last piece first, then clears `tail`.
+fn write_pieces(head: &[u8], tail: &mut Vec<Vec<u8>>, out: &mut impl Write) -> io::Result<()> {
+    if tail.is_empty() {
+        return write_line(head, out);
+    }
+    let mut line = head.to_vec();
+    for piece in tail.drain(..).rev() {
+        line.extend_from_slice(&piece);
+    }
+    write_line(&line, out)
+}
+
+fn tac_seekable(mut file: SeekableFile, out: &mut impl Write) -> io::Result<()> {
+    let len = file.len();
+    if len == 0 {
+        return Ok(());
+    }
+    let mut buf = vec![0u8; CHUNK];
+    // The end of a line longer than what has been read so far.
+    let mut pending: Vec<Vec<u8>> = Vec::new();
+    let mut pos = len;
+    let mut skip_trailing = true;
+    while pos > 0 {
+        let size = CHUNK.min(usize::try_from(pos).unwrap_or(CHUNK));
+        let start = pos - size as u64;
+        let got = file.read_at(start, &mut buf[..size])?;
+        let chunk = &buf[..got];
+        let mut end = chunk.len();
+        for i in (0..chunk.len()).rev() {
+            if chunk[i] != b'\n' {
+                continue;
+            }
+            if skip_trailing && start + i as u64 + 1 == len {
+                skip_trailing = false;
+                end = i;
+                continue;
+            }
+            write_pieces(&chunk[i + 1..end], &mut pending, out)?;
+            end = i;
+        }
+        skip_trailing = false;
+        pending.push(chunk[..end].to_vec());
+        pos = start;
+    }
+    write_pieces(&[], &mut pending, out)
+}
+
 fn run() -> io::Result<()> {
-    let files = parse_args();
+    let (files, from_list) = parse_args();
     let stdout = io::stdout();
     let mut out = stdout.lock();
 
-    if files.is_empty() {
+    if files.is_empty() && !from_list {
         let reader = encoding::open_stdin()?;
         tac(reader, &mut out)?;
     } else {
         for path in &files {
-            let reader = encoding::open_input(path).unwrap_or_else(|e| {
+            let input = input::open(path).unwrap_or_else(|e| {
                 error::err(TOOL, &format!("{path}: {e}"));
             });
-            tac(reader, &mut out)?;
+            match input {
+                Input::Seekable(f) => tac_seekable(f, &mut out)?,
+                Input::Stream(r) => tac(r, &mut out)?,
+            }
         }
     }
     Ok(())
@@ -113,6 +182,45 @@ mod tests {
         assert!(out.is_empty());
     }
 
+    fn seekable(content: &[u8]) -> (tempfile::NamedTempFile, SeekableFile) {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        f.write_all(content).unwrap();
+        f.flush().unwrap();
+        match input::open(f.path().to_str().unwrap()).unwrap() {
+            Input::Seekable(s) => (f, s),
+            Input::Stream(_) => panic!("expected seekable input"),
+        }
+    }
+
+    #[test]
+    fn seekable_matches_buffered() {
+        let inputs: &[&str] = &["", "\n", "a\n\nb", "a\r\nb\r\n", "one\ntwo\nthree\n"];
+        for input in inputs {
+            let (_f, s) = seekable(input.as_bytes());
+            let mut seek_out = Vec::new();
+            tac_seekable(s, &mut seek_out).unwrap();
+            let mut buf_out = Vec::new();
+            tac(make_reader(input), &mut buf_out).unwrap();
+            assert_eq!(seek_out, buf_out, "input {input:?}");
+        }
+    }
+
+    #[test]
+    fn seekable_lines_across_chunks() {
+        // Several chunks long, with bytes that show pieces out of order.
+        let long: String = (0..3 * CHUNK + 10)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+        let input = format!("first\n{long}\nlast\n");
+        let (_f, s) = seekable(input.as_bytes());
+        let mut out = Vec::new();
+        tac_seekable(s, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("last\n{long}\nfirst\n")
+        );
+    }
+
     #[test]
     fn no_trailing_newline() {
         let input = "a\nb\nc";
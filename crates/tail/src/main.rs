@@ -1,6 +1,10 @@
 use common::cli;
 use common::encoding;
 use common::error;
+use common::filelist::{self, Delimiter};
+use common::header::{self, HeaderArg, HeaderOpts};
+use common::input::{self, Input, SeekableFile};
+use common::interrupt;
 use common::lexopt;
 use common::lexopt::prelude::*;
 use std::collections::VecDeque;
@@ -8,12 +12,20 @@ use std::io::{self, BufRead, Read, Write};
 
 const TOOL: &str = "tail";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+const CHUNK: usize = 64 * 1024;
 
 enum Mode {
     Lines(usize),
     Bytes(usize),
 }
 
+struct Opts {
+    mode: Mode,
+    headers: HeaderOpts,
+    files: Vec<String>,
+    from_list: bool,
+}
+
 fn expand_dash_n(args: Vec<String>) -> Vec<String> {
     let mut out = Vec::new();
     for arg in args {
@@ -27,9 +39,11 @@ fn expand_dash_n(args: Vec<String>) -> Vec<String> {
     out
 }
 
-fn parse_args() -> (Mode, Vec<String>) {
+fn parse_args() -> Opts {
     let mut mode = Mode::Lines(10);
+    let mut headers = HeaderOpts::default();
     let mut files = Vec::new();
+    let mut from_list = false;
     let raw_args: Vec<String> = std::env::args().collect();
     let expanded = expand_dash_n(raw_args[1..].to_vec());
     let mut parser = lexopt::Parser::from_args(expanded);
@@ -38,6 +52,10 @@ fn parse_args() -> (Mode, Vec<String>) {
         .next()
         .unwrap_or_else(|e| error::err(TOOL, &e.to_string()))
     {
+        if let Some(header_arg) = HeaderArg::of(&arg) {
+            headers.apply(TOOL, header_arg, &mut parser);
+            continue;
+        }
         match arg {
             Short('n') | Long("lines") => {
                 let val: usize = parser
@@ -55,6 +73,14 @@ fn parse_args() -> (Mode, Vec<String>) {
                     .unwrap_or_else(|e| error::err(TOOL, &e.to_string()));
                 mode = Mode::Bytes(val);
             }
+            Long("files-from") => {
+                files.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Newline));
+                from_list = true;
+            }
+            Long("files0-from") => {
+                files.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Nul));
+                from_list = true;
+            }
             Long("help") => {
                 cli::print_help(TOOL, "output the last part of files");
                 std::process::exit(0);
@@ -73,13 +99,19 @@ fn parse_args() -> (Mode, Vec<String>) {
         }
     }
 
-    (mode, files)
+    Opts {
+        mode,
+        headers,
+        files,
+        from_list,
+    }
 }
 
 fn tail_lines_buffered(reader: impl BufRead, n: usize, out: &mut impl Write) -> io::Result<()> {
     let mut ring: VecDeque<String> = VecDeque::with_capacity(n);
     for line in reader.lines() {
         let line = line?;
+        interrupt::check()?;
         if ring.len() == n {
             ring.pop_front();
         }
@@ -99,40 +131,88 @@ fn tail_bytes_buffered(mut reader: impl Read, n: usize, out: &mut impl Write) ->
     Ok(())
 }
 
+fn last_lines_offset(file: &mut SeekableFile, n: usize) -> io::Result<u64> {
+    let len = file.len();
+    if n == 0 {
+        return Ok(len);
+    }
+    let mut buf = vec![0u8; CHUNK];
+    let mut pos = len;
+    let mut seen = 0;
+    while pos > 0 {
+        interrupt::check()?;
+        let size = CHUNK.min(usize::try_from(pos).unwrap_or(CHUNK));
+        let start = pos - size as u64;
+        let got = file.read_at(start, &mut buf[..size])?;
+        for i in (0..got).rev() {
+            let at = start + i as u64;
+            if buf[i] == b'\n' && at + 1 != len {
+                seen += 1;
+                if seen == n {
+                    return Ok(at + 1);
+                }
+            }
+        }
+        pos = start;
+    }
+    Ok(0)
+}
+
+fn tail_lines_seekable(mut file: SeekableFile, n: usize, out: &mut impl Write) -> io::Result<()> {
+    let offset = last_lines_offset(&mut file, n)?;
+    tail_lines_buffered(file.reader_from(offset)?, n, out)
+}
+
+fn tail_bytes_seekable(file: SeekableFile, n: usize, out: &mut impl Write) -> io::Result<()> {
+    let offset = file.len().saturating_sub(n as u64);
+    io::copy(&mut file.reader_from(offset)?, out)?;
+    Ok(())
+}
+
 fn run() -> io::Result<()> {
-    let (mode, files) = parse_args();
+    let opts = parse_args();
     let stdout = io::stdout();
     let mut out = stdout.lock();
-    let multiple = files.len() > 1;
+    let files = if opts.files.is_empty() && !opts.from_list {
+        vec!["-".to_string()]
+    } else {
+        opts.files
+    };
+    let mut header = opts.headers.header();
+    let show_headers = header.enabled(files.len());
 
-    if files.is_empty() {
-        let reader = encoding::open_stdin()?;
-        match &mode {
-            Mode::Lines(n) => tail_lines_buffered(reader, *n, &mut out)?,
-            Mode::Bytes(n) => tail_bytes_buffered(reader, *n, &mut out)?,
+    for path in &files {
+        if show_headers {
+            header.write(&mut out, header::label(path))?;
         }
-    } else {
-        for (i, path) in files.iter().enumerate() {
-            if multiple {
-                if i > 0 {
-                    writeln!(out)?;
-                }
-                writeln!(out, "==> {path} <==")?;
-            }
-            let reader = encoding::open_input(path).unwrap_or_else(|e| {
-                error::err(TOOL, &format!("{path}: {e}"));
-            });
-            match &mode {
+        if path == "-" {
+            let reader = encoding::open_reader(interrupt::Checked(io::stdin()));
+            match &opts.mode {
                 Mode::Lines(n) => tail_lines_buffered(reader, *n, &mut out)?,
                 Mode::Bytes(n) => tail_bytes_buffered(reader, *n, &mut out)?,
             }
+            continue;
+        }
+        let input = input::open(path).unwrap_or_else(|e| {
+            error::err(TOOL, &format!("{path}: {e}"));
+        });
+        match (input, &opts.mode) {
+            (Input::Seekable(f), Mode::Lines(n)) => tail_lines_seekable(f, *n, &mut out)?,
+            (Input::Seekable(f), Mode::Bytes(n)) => tail_bytes_seekable(f, *n, &mut out)?,
+            (Input::Stream(r), Mode::Lines(n)) => tail_lines_buffered(r, *n, &mut out)?,
+            (Input::Stream(r), Mode::Bytes(n)) => tail_bytes_buffered(r, *n, &mut out)?,
         }
     }
     Ok(())
 }
 
 fn main() {
-    if let Err(e) = run() {
+    interrupt::install();
+    let result = run();
+    if interrupt::interrupted() {
+        std::process::exit(interrupt::exit_code());
+    }
+    if let Err(e) = result {
         if e.kind() != io::ErrorKind::BrokenPipe {
             error::err(TOOL, &e.to_string());
         }
@@ -214,6 +294,47 @@ mod tests {
         assert_eq!(expanded, vec!["-n", "10"]);
     }
 
+    fn seekable(content: &[u8]) -> (tempfile::NamedTempFile, SeekableFile) {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        f.write_all(content).unwrap();
+        f.flush().unwrap();
+        match input::open(f.path().to_str().unwrap()).unwrap() {
+            Input::Seekable(s) => (f, s),
+            Input::Stream(_) => panic!("expected seekable input"),
+        }
+    }
+
+    #[test]
+    fn seekable_offset_ignores_trailing_newline() {
+        let (_f, mut s) = seekable(b"a\nb\nc\n");
+        assert_eq!(last_lines_offset(&mut s, 2).unwrap(), 2);
+        assert_eq!(last_lines_offset(&mut s, 5).unwrap(), 0);
+        assert_eq!(last_lines_offset(&mut s, 0).unwrap(), 6);
+    }
+
+    #[test]
+    fn seekable_lines_across_chunks() {
+        let input = (1..=20000)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        let (_f, s) = seekable(input.as_bytes());
+        let mut out = Vec::new();
+        tail_lines_seekable(s, 3, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "line 19998\nline 19999\nline 20000\n"
+        );
+    }
+
+    #[test]
+    fn seekable_bytes() {
+        let (_f, s) = seekable(b"hello world\n");
+        let mut out = Vec::new();
+        tail_bytes_seekable(s, 6, &mut out).unwrap();
+        assert_eq!(out, b"world\n");
+    }
+
     #[test]
     fn stdin_ring_buffer() {
         let input = (1..=100)
@@ -1,3 +1,4 @@
+use common::interrupt;
 use lexopt::prelude::*;
 use std::fs::{File, OpenOptions};
 use std::io::{self, Read, Write};
@@ -8,12 +9,14 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 struct Opts {
     files: Vec<String>,
     append: bool,
+    ignore_interrupts: bool,
 }
 
 fn parse_args() -> Opts {
     let mut parser = lexopt::Parser::from_env();
     let mut files = Vec::new();
     let mut append = false;
+    let mut ignore_interrupts = false;
 
     while let Some(arg) = parser.next().unwrap_or_else(|e| {
         eprintln!("{TOOL}: {e}");
@@ -21,6 +24,7 @@ fn parse_args() -> Opts {
     }) {
         match arg {
             Short('a') | Long("append") => append = true,
+            Short('i') | Long("ignore-interrupts") => ignore_interrupts = true,
             Long("help") => {
                 println!("{TOOL} - {USAGE}");
                 std::process::exit(0);
@@ -40,7 +44,11 @@ fn parse_args() -> Opts {
         }
     }
 
-    Opts { files, append }
+    Opts {
+        files,
+        append,
+        ignore_interrupts,
+    }
 }
 
 const USAGE: &str = "\
@@ -49,12 +57,16 @@ Usage: tee [OPTIONS] [FILE...]
 Copy stdin to stdout and each FILE.
 
 Options:
-  -a, --append  Append to files instead of overwriting
-      --help    Show this help
-      --version Show version";
+  -a, --append             Append to files instead of overwriting
+  -i, --ignore-interrupts  Ignore Ctrl-C (SIGINT)
+      --help               Show this help
+      --version            Show version";
 
 fn run() -> io::Result<()> {
     let opts = parse_args();
+    if opts.ignore_interrupts {
+        interrupt::ignore_sigint();
+    }
 
     let mut outputs: Vec<Box<dyn Write>> = Vec::new();
     for path in &opts.files {
@@ -67,12 +79,13 @@ fn run() -> io::Result<()> {
     }
 
     let stdin = io::stdin();
+    let mut stdin = interrupt::Checked(stdin.lock());
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
     let mut buf = [0u8; 8192];
 
     loop {
-        let n = stdin.lock().read(&mut buf)?;
+        let n = stdin.read(&mut buf)?;
         if n == 0 {
             break;
         }
@@ -88,7 +101,12 @@ fn run() -> io::Result<()> {
 }
 
 fn main() {
-    if let Err(e) = run() {
+    interrupt::install();
+    let result = run();
+    if interrupt::interrupted() {
+        std::process::exit(interrupt::exit_code());
+    }
+    if let Err(e) = result {
         if e.kind() != io::ErrorKind::BrokenPipe {
             eprintln!("{TOOL}: {e}");
             std::process::exit(1);
@@ -96,3 +96,12 @@ fn missing_file() {
     assert!(!out.status.success());
     assert_stderr_contains(&out, "cat:");
 }
+
+#[test]
+fn files_from_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = fixture_list(dir.path(), &["five-lines.txt", "five-lines.txt"], "\n");
+    let out = run("cat", &["--files-from", list.to_str().unwrap()]);
+    assert_exit_success(&out);
+    assert_stdout(&out, &"one\ntwo\nthree\nfour\nfive\n".repeat(2));
+}
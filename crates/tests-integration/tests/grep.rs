@@ -108,6 +108,56 @@ fn stdin() {
     );
 }
 
+#[test]
+fn stdin_label_and_dash() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.txt");
+    std::fs::write(&file, "fox in a file\n").unwrap();
+    let file = file.to_str().unwrap();
+    let input = b"fox on stdin\n";
+    run_stdin_cases(
+        "grep",
+        &[
+            StdinCase {
+                name: "-H names stdin",
+                args: args(&["-H", "fox"]),
+                stdin: input,
+                expected: "(standard input):fox on stdin\n",
+            },
+            StdinCase {
+                name: "--label",
+                args: args(&["-Hn", "--label=pipe", "fox"]),
+                stdin: input,
+                expected: "pipe:1:fox on stdin\n",
+            },
+            StdinCase {
+                name: "--label alone does not add a prefix",
+                args: args(&["--label=pipe", "fox"]),
+                stdin: input,
+                expected: "fox on stdin\n",
+            },
+            StdinCase {
+                name: "-l lists stdin",
+                args: args(&["-l", "fox"]),
+                stdin: input,
+                expected: "(standard input)\n",
+            },
+            StdinCase {
+                name: "- with filters and -r",
+                args: args(&["-r", "--include=*.rs", "-c", "fox", "-"]),
+                stdin: input,
+                expected: "1\n",
+            },
+        ],
+    );
+
+    let out = run_with_stdin("grep", &["--label=pipe", "fox", file, "-"], input);
+    assert_stdout(&out, &format!("{file}:fox in a file\npipe:fox on stdin\n"));
+
+    let out = run("grep", &["--replace=x", "--in-place", "fox", "-"]);
+    assert_stderr_contains(&out, "cannot rewrite standard input");
+}
+
 #[test]
 fn exit_codes() {
     let f = fixture("five-lines.txt").to_str().unwrap().to_string();
@@ -128,3 +178,657 @@ fn utf16le_bom() {
     assert_exit_success(&out);
     assert_stdout_contains(&out, "hello");
 }
+
+#[test]
+fn files_from_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = fixture_list(dir.path(), &["words.txt", "five-lines.txt"], "\n");
+    let out = run("grep", &["-l", "o", "--files-from", list.to_str().unwrap()]);
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(s.contains("words.txt") && s.contains("five-lines.txt"));
+
+    let names = format!("{}\n", fixture("words.txt").display());
+    let out = run_with_stdin("grep", &["quick", "--files-from=-"], names.as_bytes());
+    assert_exit_success(&out);
+    assert_stdout(&out, "the quick brown fox\n");
+}
+
+#[test]
+fn context_lines() {
+    let f = fixture("five-lines.txt").to_str().unwrap().to_string();
+    let cases: &[(&str, &[&str], &str)] = &[
+        ("-A 1", &["-A", "1", "two"], "two\nthree\n"),
+        ("-B 1 -n", &["-B", "1", "-n", "three"], "2-two\n3:three\n"),
+        (
+            "-C 1 groups",
+            &["-C", "1", r"one\|five"],
+            "one\ntwo\n--\nfour\nfive\n",
+        ),
+        (
+            "custom separator",
+            &["-A", "1", "--group-separator=##", r"one\|four"],
+            "one\ntwo\n##\nfour\nfive\n",
+        ),
+        (
+            "no separator",
+            &["-A", "1", "--no-group-separator", r"one\|four"],
+            "one\ntwo\nfour\nfive\n",
+        ),
+    ];
+    for (name, flag_args, expected) in cases {
+        let mut a: Vec<String> = flag_args.iter().map(|s| s.to_string()).collect();
+        a.push(f.clone());
+        run_cases(
+            "grep",
+            &[Case {
+                name,
+                args: a,
+                expected,
+            }],
+        );
+    }
+
+    let f2 = fixture("words.txt").to_str().unwrap().to_string();
+    let out = run("grep", &["-n", "-A", "1", "quick", &f2, &f]);
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(
+        s.contains("words.txt:1:the quick brown fox\n"),
+        "got: {s:?}"
+    );
+    assert!(s.contains("words.txt-2-jumps over\n"), "got: {s:?}");
+}
+
+#[test]
+fn match_positions() {
+    let input = b"id 12 and 345\r\nnone\n7\n";
+    let cases: &[(&str, &[&str], &str)] = &[
+        ("-o", &["-oE", "[0-9]+"], "12\n345\n7\n"),
+        ("-o -n", &["-onE", "[0-9]+"], "1:12\n1:345\n3:7\n"),
+        ("-b", &["-b", "[0-9]"], "0:id 12 and 345\n20:7\n"),
+        ("-o -b", &["-obE", "[0-9]+"], "3:12\n10:345\n20:7\n"),
+        (
+            "--column",
+            &["-n", "--column", r"and\|7"],
+            "1:7:id 12 and 345\n3:1:7\n",
+        ),
+    ];
+    for (name, flag_args, expected) in cases {
+        let out = run_with_stdin("grep", flag_args, input);
+        assert_exit_success(&out);
+        assert_eq!(stdout_str(&out), *expected, "case: {name}");
+    }
+}
+
+#[test]
+fn fixed_and_multiple_patterns() {
+    let input = b"x = a.b[0];\nx = axb[0];\nfoo\nbar\n";
+    let cases: &[(&str, &[&str], &str)] = &[
+        ("-F literal", &["-F", "a.b[0]"], "x = a.b[0];\n"),
+        ("-e twice", &["-e", "foo", "-e", "^bar"], "foo\nbar\n"),
+        (
+            "-F -e",
+            &["-F", "-e", "[0]", "-e", "bar"],
+            "x = a.b[0];\nx = axb[0];\nbar\n",
+        ),
+        ("-F -i", &["-Fi", "FOO"], "foo\n"),
+    ];
+    for (name, flag_args, expected) in cases {
+        let out = run_with_stdin("grep", flag_args, input);
+        assert_exit_success(&out);
+        assert_eq!(stdout_str(&out), *expected, "case: {name}");
+    }
+}
+
+#[test]
+fn pattern_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let utf16 = dir.path().join("patterns.txt");
+    let mut data = vec![0xFF, 0xFE];
+    data.extend(
+        "quick\r\nlazy\r\n"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes()),
+    );
+    std::fs::write(&utf16, data).unwrap();
+    let f = fixture("words.txt").to_str().unwrap().to_string();
+
+    let out = run("grep", &["-f", utf16.to_str().unwrap(), &f]);
+    assert_exit_success(&out);
+    assert_stdout(&out, "the quick brown fox\nthe lazy dog\n");
+
+    let empty = dir.path().join("empty.txt");
+    std::fs::write(&empty, "").unwrap();
+    assert_exit_code(&run("grep", &["-f", empty.to_str().unwrap(), &f]), 1);
+
+    let out = run("grep", &["-f", "/nonexistent_path_xyz/p.txt", &f]);
+    assert_stderr_contains(&out, "grep:");
+}
+
+#[test]
+fn word_and_line_regexp() {
+    let input = b"valid\nid = 1\nuser_id\nID\nid\n";
+    let cases: &[(&str, &[&str], &str)] = &[
+        ("-w", &["-w", "id"], "id = 1\nid\n"),
+        ("-w -i", &["-wi", "id"], "id = 1\nID\nid\n"),
+        ("-x", &["-x", "id"], "id\n"),
+        ("-x -F -i", &["-xFi", "ID"], "ID\nid\n"),
+        (
+            "-w -o -n",
+            &["-won", "[a-z][a-z]*"],
+            "1:valid\n2:id\n5:id\n",
+        ),
+    ];
+    for (name, flag_args, expected) in cases {
+        let out = run_with_stdin("grep", flag_args, input);
+        assert_exit_success(&out);
+        assert_eq!(stdout_str(&out), *expected, "case: {name}");
+    }
+}
+
+#[test]
+fn include_exclude_globs() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    needle_tree(
+        root,
+        &[
+            "src/main.rs",
+            "src/notes.md",
+            "src/gen.rs",
+            "target/debug/build.rs",
+            "node_modules/pkg/index.js",
+        ],
+    );
+
+    assert_eq!(grep_listed(root, &[]).len(), 5);
+    assert_eq!(
+        grep_listed(root, &["--include=*.rs", "--exclude-dir=target"]),
+        vec!["src/gen.rs", "src/main.rs"]
+    );
+    assert_eq!(
+        grep_listed(
+            root,
+            &[
+                "--exclude-dir=target",
+                "--exclude-dir=node_*",
+                "--exclude=*.md"
+            ]
+        ),
+        vec!["src/gen.rs", "src/main.rs"]
+    );
+
+    let excludes = root.join("excludes.txt");
+    std::fs::write(&excludes, "gen.*\r\n*.md\r\n*.js\r\n").unwrap();
+    let from = format!("--exclude-from={}", excludes.display());
+    assert_eq!(
+        grep_listed(root, &[&from, "--exclude=*.txt", "--exclude-dir=target"]),
+        vec!["src/main.rs"]
+    );
+
+    // Command-line files are filtered too.
+    let main = root.join("src/main.rs");
+    let out = run(
+        "grep",
+        &["--exclude=*.rs", "needle", main.to_str().unwrap()],
+    );
+    assert_exit_code(&out, 1);
+}
+
+#[test]
+fn respect_gitignore_and_hidden() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir(root.join(".git")).unwrap();
+    std::fs::write(root.join(".gitignore"), "bin/\n*.log\n!keep.log\n").unwrap();
+    needle_tree(
+        root,
+        &[
+            "src/main.rs",
+            "bin/app.rs",
+            "debug.log",
+            "keep.log",
+            ".vscode/settings.json",
+        ],
+    );
+
+    assert_eq!(
+        grep_listed(root, &[]),
+        vec!["bin/app.rs", "debug.log", "keep.log", "src/main.rs"]
+    );
+    assert_eq!(
+        grep_listed(root, &["--respect-gitignore"]),
+        vec!["keep.log", "src/main.rs"]
+    );
+    assert_eq!(
+        grep_listed(root, &["--respect-gitignore", "--hidden"]),
+        vec![".vscode/settings.json", "keep.log", "src/main.rs"]
+    );
+}
+
+#[test]
+fn binary_files() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["utf16le-bom.txt", "binary.bin"] {
+        std::fs::copy(fixture(name), dir.path().join(name)).unwrap();
+    }
+    std::fs::write(dir.path().join("tool.exe"), b"MZ\0\0hello\0").unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    // UTF-16 text is searched, not mistaken for binary.
+    let out = run("grep", &["-r", "hello", root]);
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(s.contains("utf16le-bom.txt:hello"), "got: {s:?}");
+    assert!(
+        s.contains("Binary file ") && s.contains("tool.exe matches"),
+        "got: {s:?}"
+    );
+
+    let out = run("grep", &["-r", "-I", "hello", root]);
+    let s = stdout_str(&out);
+    assert!(!s.contains("tool.exe"), "got: {s:?}");
+
+    let exe = dir.path().join("tool.exe");
+    let out = run(
+        "grep",
+        &[
+            "-c",
+            "--binary-files=without-match",
+            "hello",
+            exe.to_str().unwrap(),
+        ],
+    );
+    assert_exit_code(&out, 1);
+
+    let out = run_with_stdin("grep", &["-a", "hello"], b"MZ\0hello\n");
+    assert_stdout(&out, "MZ\0hello\n");
+
+    let out = run("grep", &["--binary-files=nope", "x", root]);
+    assert_stderr_contains(&out, "--binary-files");
+}
+
+#[test]
+fn parallel_search_order() {
+    let dir = tempfile::tempdir().unwrap();
+    for d in 0..5 {
+        let sub = dir.path().join(format!("d{d}"));
+        std::fs::create_dir_all(&sub).unwrap();
+        for f in 0..20 {
+            let text = format!("needle {d}-{f}\nhay\nneedle again\n");
+            std::fs::write(sub.join(format!("f{f:02}.txt")), text).unwrap();
+        }
+    }
+    let root = dir.path().to_str().unwrap();
+
+    let sequential = run("grep", &["-rn", "-j", "1", "needle", root]);
+    assert_exit_success(&sequential);
+    let expected = stdout_str(&sequential);
+    assert_eq!(expected.lines().count(), 200);
+
+    for _ in 0..3 {
+        let out = run("grep", &["-rn", "-j", "4", "needle", root]);
+        assert_eq!(stdout_str(&out), expected);
+    }
+
+    let out = run(
+        "grep",
+        &["-rn", "--threads=4", "--sort=none", "needle", root],
+    );
+    assert_exit_success(&out);
+    let mut got: Vec<String> = stdout_str(&out).lines().map(String::from).collect();
+    let mut want: Vec<String> = expected.lines().map(String::from).collect();
+    got.sort();
+    want.sort();
+    assert_eq!(got, want);
+
+    let out = run("grep", &["-r", "--sort=size", "needle", root]);
+    assert_stderr_contains(&out, "--sort");
+}
+
+#[test]
+fn recursive_names_files_in_single_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("only.txt"), "needle\n").unwrap();
+    let out = run("grep", &["-r", "needle", dir.path().to_str().unwrap()]);
+    assert_exit_success(&out);
+    assert_stdout_contains(&out, "only.txt:needle");
+}
+
+#[test]
+fn basic_and_extended_regexp() {
+    let input = b"a+b\naab\nab|cd\ncd\nx2024y\nTab\there\n";
+    let cases: &[(&str, &[&str], &str)] = &[
+        ("BRE literal +", &["a+b"], "a+b\n"),
+        ("BRE \\+", &[r"^a\+b$"], "aab\n"),
+        ("ERE +", &["-E", "a+b"], "aab\nab|cd\nTab\there\n"),
+        ("BRE literal |", &["ab|cd"], "ab|cd\n"),
+        ("ERE |", &["-E", "^(ab|cd)$"], "cd\n"),
+        ("BRE interval", &[r"[0-9]\{4\}"], "x2024y\n"),
+        ("-G after -E", &["-E", "-G", "a+b"], "a+b\n"),
+        (
+            "POSIX class",
+            &["^[[:upper:]][[:lower:]]*[[:blank:]]"],
+            "Tab\there\n",
+        ),
+        ("leading *", &["-c", "*"], "0\n"),
+    ];
+    for (name, args, expected) in cases {
+        let out = run_with_stdin("grep", args, input);
+        assert_eq!(stdout_str(&out), *expected, "case: {name}");
+    }
+
+    let out = run_with_stdin("grep", &[r"\(a\)\1"], input);
+    assert_exit_code(&out, 2);
+    assert_stderr_contains(&out, "back-references");
+
+    let out = run_with_stdin("grep", &["[:space:]"], input);
+    assert_exit_code(&out, 2);
+    assert_stderr_contains(&out, "[[:space:]]");
+}
+
+#[test]
+fn max_count_quiet_and_file_names() {
+    let words = fixture("words.txt").to_str().unwrap().to_string();
+    let five = fixture("five-lines.txt").to_str().unwrap().to_string();
+    let missing = "/nonexistent_path_xyz/none.txt";
+
+    let out = run("grep", &["-m", "1", "the", &words]);
+    assert_exit_success(&out);
+    assert_stdout(&out, "the quick brown fox\n");
+    assert_exit_code(&run("grep", &["-m", "0", "the", &words]), 1);
+
+    let out = run("grep", &["-q", "the", &words, missing]);
+    assert_exit_success(&out);
+    assert_stdout(&out, "");
+    assert_exit_code(&run("grep", &["-q", "zzz", &words]), 1);
+
+    let out = run("grep", &["-s", "the", missing]);
+    assert_exit_code(&out, 1);
+    assert_eq!(String::from_utf8_lossy(&out.stderr), "");
+    assert_stderr_contains(&run("grep", &["the", missing]), "none.txt");
+
+    let out = run("grep", &["-L", "quick", &words, &five]);
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(
+        s.contains("five-lines.txt") && !s.contains("words.txt"),
+        "{s}"
+    );
+    assert_exit_code(&run("grep", &["-L", "quick", &words]), 1);
+
+    let out = run("grep", &["-H", "quick", &words]);
+    assert_stdout_contains(&out, "words.txt:the quick brown fox");
+    let out = run("grep", &["-h", "-j", "2", "quick", &words, &five]);
+    assert_stdout(&out, "the quick brown fox\n");
+}
+
+#[test]
+fn heading_vimgrep_and_null() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "one\nfoo foo\n").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "none\n").unwrap();
+    std::fs::write(dir.path().join("c.txt"), "foo\n").unwrap();
+    let root = dir.path().to_str().unwrap();
+    let a = format!("{root}/a.txt");
+    let c = format!("{root}/c.txt");
+
+    for threads in ["1", "4"] {
+        let out = run("grep", &["-n", "--heading", "-j", threads, "foo", &a, &c]);
+        assert_exit_success(&out);
+        assert_stdout(&out, &format!("{a}\n  2:foo foo\n\n{c}\n  1:foo\n"));
+    }
+    let out = run("grep", &["--heading", "foo", &c]);
+    assert_stdout(&out, "foo\n");
+
+    let out = run("grep", &["--vimgrep", "foo", &a]);
+    assert_stdout(&out, &format!("{a}:2:1:foo foo\n{a}:2:5:foo foo\n"));
+
+    let out = run("grep", &["-lZ", "foo", &a, &c]);
+    assert_stdout(&out, &format!("{a}\0{c}\0"));
+    let out = run("grep", &["--null", "-c", "foo", &a]);
+    assert_stdout(&out, "1\n");
+    let out = run("grep", &["--null", "-H", "foo", &c]);
+    assert_stdout(&out, &format!("{c}\0foo\n"));
+}
+
+#[test]
+fn replace_and_in_place() {
+    let input = b"id = old_name(1);\nkeep\n";
+    let out = run_with_stdin(
+        "grep",
+        &[
+            "-E",
+            "--replace",
+            "new_name(${n})",
+            r"old_name\((?<n>[0-9])\)",
+        ],
+        input,
+    );
+    assert_exit_success(&out);
+    assert_stdout(&out, "id = new_name(1);\n");
+    let out = run_with_stdin("grep", &["-o", "--replace", "<$0>", "old"], input);
+    assert_stdout(&out, "<old>\n");
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    let mut utf16 = vec![0xFF, 0xFE];
+    utf16.extend(
+        "old\r\nkeep\r\nold old\r\n"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes()),
+    );
+    std::fs::write(&path, &utf16).unwrap();
+    let p = path.to_str().unwrap();
+    let out = run("grep", &["--in-place", "--replace", "new", "old", p]);
+    assert_exit_success(&out);
+    assert_stdout(&out, "");
+    let mut expected = vec![0xFF, 0xFE];
+    expected.extend(
+        "new\r\nkeep\r\nnew new\r\n"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes()),
+    );
+    assert_eq!(std::fs::read(&path).unwrap(), expected);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    let out = run("grep", &["--in-place", "old", p]);
+    assert_stderr_contains(&out, "--replace");
+    let out = run("grep", &["--in-place", "-Uv", "--replace", "x", "old", p]);
+    assert_exit_code(&out, 1);
+    assert_stderr_contains(&out, "cannot be combined with -v");
+}
+
+#[test]
+fn multiline() {
+    let input = b"fn main() {\r\n    run();\r\n}\r\nfn other() {}\r\n";
+    let out = run_with_stdin("grep", &["-nU", r"main() {\r\n *run"], input);
+    assert_exit_success(&out);
+    assert_stdout(&out, "1:fn main() {\n2:    run();\n");
+
+    let out = run_with_stdin("grep", &["-U", "-c", "-E", r"(?s)\{.*?\}"], input);
+    assert_stdout(&out, "4\n");
+
+    // Without -U, `\n` is GNU's plain `n` and lines are matched one by one.
+    assert_exit_code(&run_with_stdin("grep", &[r"{\r\n"], input), 1);
+}
+
+#[test]
+fn file_types() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    needle_tree(
+        root,
+        &[
+            "Cargo.toml",
+            "src/main.rs",
+            "src/Program.cs",
+            "src/build.ps1",
+            "src/notes.md",
+            "src/schema.proto",
+        ],
+    );
+
+    assert_eq!(grep_listed(root, &["-t", "rust"]), vec!["src/main.rs"]);
+    assert_eq!(
+        grep_listed(root, &["-t", "cs", "--type=ps1", "-tcargo"]),
+        vec!["Cargo.toml", "src/Program.cs", "src/build.ps1"]
+    );
+    assert_eq!(grep_listed(root, &["-T", "markdown", "-Tcargo"]).len(), 4);
+    assert_eq!(
+        grep_listed(root, &["-t", "proto", "--type-add", "proto:*.proto"]),
+        vec!["src/schema.proto"]
+    );
+
+    // Types don't filter files named on the command line.
+    let notes = root.join("src/notes.md");
+    let out = run("grep", &["-t", "rust", "needle", notes.to_str().unwrap()]);
+    assert_exit_code(&out, 0);
+
+    let out = run("grep", &["-t", "nope", "needle", root.to_str().unwrap()]);
+    assert_stderr_contains(&out, "unknown file type 'nope'");
+
+    let out = run("grep", &["--type-add=proto:*.proto", "--type-list"]);
+    assert_exit_code(&out, 0);
+    let list = stdout_str(&out);
+    assert!(list.contains("rust: *.rs\n"));
+    assert!(list.contains("proto: *.proto\n"));
+}
+
+#[test]
+fn max_depth() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+    for file in ["top.txt", "a/mid.txt", "a/b/deep.txt"] {
+        std::fs::write(dir.path().join(file), "needle\n").unwrap();
+    }
+    let root = dir.path().to_str().unwrap();
+    let out = run("grep", &["-rc", "--max-depth=2", "needle", root]);
+    let found = stdout_str(&out);
+    assert_eq!(found.lines().count(), 2);
+    assert!(!found.contains("deep.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_and_loops() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src/sub")).unwrap();
+    std::fs::create_dir_all(root.join("shared")).unwrap();
+    std::fs::write(root.join("src/main.rs"), "needle\n").unwrap();
+    std::fs::write(root.join("shared/util.rs"), "needle\n").unwrap();
+    symlink(root.join("shared"), root.join("src/shared")).unwrap();
+    symlink(root.join("src"), root.join("src/sub/up")).unwrap();
+    let src = root.join("src");
+
+    let out = run("grep", &["-rl", "needle", src.to_str().unwrap()]);
+    assert_exit_code(&out, 0);
+    assert_eq!(stdout_str(&out).lines().count(), 1);
+    assert!(stderr_str(&out).is_empty());
+
+    // -R follows the link to shared/ and reports the loop instead of
+    // recursing forever.
+    let out = run("grep", &["-Rl", "needle", src.to_str().unwrap()]);
+    assert_exit_code(&out, 0);
+    assert_eq!(stdout_str(&out).lines().count(), 2);
+    assert_stderr_contains(&out, "recursive directory loop");
+
+    // A link named on the command line is followed even with -r.
+    let linked = src.join("shared");
+    let out = run("grep", &["-rl", "needle", linked.to_str().unwrap()]);
+    assert_stdout_contains(&out, "util.rs");
+}
+
+#[test]
+fn stats() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(root.join("a.txt"), "one two\ntwo two\nthree\n").unwrap();
+    std::fs::write(root.join("b.txt"), "two\n").unwrap();
+    std::fs::write(root.join("c.bin"), "two\0\n").unwrap();
+    std::fs::write(root.join("skip.md"), "two\n").unwrap();
+    let root_str = root.to_str().unwrap();
+
+    let out = run(
+        "grep",
+        &["-r", "-I", "--exclude=*.md", "--stats", "two", root_str],
+    );
+    assert_exit_code(&out, 0);
+    assert_eq!(stdout_str(&out).lines().count(), 3);
+    let err = stderr_str(&out);
+    for line in [
+        "3 files searched\n",
+        "1 binary files\n",
+        "1 files and directories excluded\n",
+        "31 bytes read\n",
+        "3 lines selected\n",
+        "4 matches\n",
+        " seconds elapsed\n",
+    ] {
+        assert!(err.contains(line), "missing {line:?} in {err:?}");
+    }
+
+    let a = root.join("a.txt");
+    let missing = root.join("missing.txt");
+    let out = run(
+        "grep",
+        &[
+            "-c",
+            "--stats=json",
+            "two",
+            a.to_str().unwrap(),
+            missing.to_str().unwrap(),
+        ],
+    );
+    let err = stderr_str(&out);
+    assert!(err.contains("{\"type\":\"summary\",\"files_searched\":1,"));
+    assert!(err.contains("\"files_unreadable\":1,"));
+    assert!(err.contains("\"matched_lines\":2,\"matches\":3,"));
+    assert!(err.trim_end().ends_with('}'));
+}
+
+#[test]
+fn search_zip() {
+    let zip = fixture("archive.zip").to_str().unwrap().to_string();
+    let docx = fixture("spec.docx").to_str().unwrap().to_string();
+
+    // Without --search-zip an archive is just a binary file.
+    let out = run("grep", &["fox", &zip]);
+    assert_stdout(&out, &format!("Binary file {zip} matches\n"));
+
+    let out = run("grep", &["--search-zip", "-n", "fox", &zip, &docx]);
+    assert_exit_code(&out, 0);
+    assert_stdout(
+        &out,
+        &format!(
+            "{zip}!docs/notes.txt:40:note 40: the fox was here\n\
+             {zip}!readme.txt:1:a stored fox\n\
+             Binary file {zip}!lib/fox.bin matches\n\
+             {docx}!word/document.xml:2:The quick brown fox & the lazy dog\n"
+        ),
+    );
+
+    // Entries are named even in a single archive; -I skips binary ones.
+    let out = run("grep", &["--search-zip", "-I", "-c", "fox", &zip]);
+    assert_stdout(
+        &out,
+        &format!("{zip}!docs/notes.txt:1\n{zip}!readme.txt:1\n"),
+    );
+    let out = run("grep", &["--search-zip", "-h", "Design", &docx]);
+    assert_stdout(&out, "Design spec\n");
+
+    // Package metadata of Office documents is not searched.
+    let out = run("grep", &["--search-zip", "-c", "document.xml", &docx]);
+    assert_exit_code(&out, 1);
+
+    let dir = tempfile::tempdir().unwrap();
+    let fake = dir.path().join("fake.zip");
+    std::fs::write(&fake, "fox, but not a zip\n").unwrap();
+    let out = run("grep", &["--search-zip", "fox", fake.to_str().unwrap()]);
+    assert_exit_code(&out, 1);
+    assert_stderr_contains(&out, "not a zip archive");
+}
@@ -94,3 +94,48 @@ fn errors() {
     assert!(!out.status.success());
     assert_stderr_contains(&out, "head:");
 }
+
+#[test]
+fn header_options() {
+    let f5 = fixture("five-lines.txt").to_str().unwrap().to_string();
+    let words = fixture("words.txt").to_str().unwrap().to_string();
+
+    let out = run("head", &["-q", "-n", "1", &f5, &words]);
+    assert_exit_success(&out);
+    assert!(!stdout_str(&out).contains("==>"));
+
+    let out = run("head", &["-v", "-n", "1", &f5]);
+    assert_exit_success(&out);
+    assert_stdout_contains(&out, &format!("==> {f5} <==\n"));
+
+    let out = run_with_stdin("head", &["-v", "-n", "1"], b"x\n");
+    assert_exit_success(&out);
+    assert_stdout(&out, "==> standard input <==\nx\n");
+
+    let out = run(
+        "head",
+        &["--header-format", "## {name}", "-n", "1", &f5, &words],
+    );
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(s.starts_with(&format!("## {f5}\n")), "got: {s:?}");
+    assert!(s.contains(&format!("\n\n## {words}\n")), "got: {s:?}");
+
+    let out = run("head", &["--color=always", "-v", "-n", "1", &f5]);
+    assert_exit_success(&out);
+    assert_stdout_contains(&out, "\x1b[1m==> ");
+}
+
+#[test]
+fn files_from_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = fixture_list(dir.path(), &["five-lines.txt", "words.txt"], "\n");
+    let out = run("head", &["-n", "1", "--files-from", list.to_str().unwrap()]);
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(s.contains("five-lines.txt <==\none\n"), "got: {s:?}");
+    assert!(
+        s.contains("words.txt <==\nthe quick brown fox\n"),
+        "got: {s:?}"
+    );
+}
@@ -202,3 +202,54 @@ pub fn pipe(tool1: &str, args1: &[&str], tool2: &str, args2: &[&str]) -> Output
     let _ = child1.wait();
     out
 }
+
+// Send a signal (e.g. "INT", "TERM") to a running child process.
+#[cfg(unix)]
+#[allow(dead_code)]
+pub fn send_signal(child: &std::process::Child, sig: &str) {
+    let status = Command::new("kill")
+        .args([format!("-{sig}"), child.id().to_string()])
+        .status()
+        .unwrap_or_else(|e| panic!("failed to run kill: {e}"));
+    assert!(status.success(), "kill -{sig} failed");
+}
+
+// Write a file list (for --files-from / --files0-from) naming the given
+// fixtures, separated by `sep`.
+#[allow(dead_code)]
+pub fn fixture_list(dir: &std::path::Path, fixtures: &[&str], sep: &str) -> PathBuf {
+    let body: String = fixtures
+        .iter()
+        .map(|f| format!("{}{sep}", fixture(f).to_str().unwrap()))
+        .collect();
+    let path = dir.join(if sep == "\0" { "list0.txt" } else { "list.txt" });
+    std::fs::write(&path, body).unwrap();
+    path
+}
+
+// Create each file (and its parent directories) under `root`, containing
+// the line "needle".
+#[allow(dead_code)]
+pub fn needle_tree(root: &std::path::Path, files: &[&str]) {
+    for file in files {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "needle\n").unwrap();
+    }
+}
+
+// Run `grep -rl needle <extra> <root>` and return the matching files
+// relative to `root`, with `/` separators, sorted.
+#[allow(dead_code)]
+pub fn grep_listed(root: &std::path::Path, extra: &[&str]) -> Vec<String> {
+    let root_str = root.to_str().unwrap();
+    let mut a = vec!["-rl", "needle"];
+    a.extend_from_slice(extra);
+    a.push(root_str);
+    let mut names: Vec<String> = stdout_str(&run("grep", &a))
+        .lines()
+        .map(|l| l[root_str.len() + 1..].replace('\\', "/"))
+        .collect();
+    names.sort();
+    names
+}
@@ -76,3 +76,12 @@ fn missing_file() {
     assert!(!out.status.success());
     assert_stderr_contains(&out, "tac:");
 }
+
+#[test]
+fn files_from_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = fixture_list(dir.path(), &["five-lines.txt"], "\0");
+    let out = run("tac", &["--files0-from", list.to_str().unwrap()]);
+    assert_exit_success(&out);
+    assert_stdout(&out, "five\nfour\nthree\ntwo\none\n");
+}
@@ -100,3 +100,82 @@ fn errors() {
     assert!(!out.status.success());
     assert_stderr_contains(&out, "tail:");
 }
+
+#[test]
+fn large_file_and_utf8_bom() {
+    let dir = tempfile::tempdir().unwrap();
+    let big = dir.path().join("big.txt");
+    let content: String = (1..=50000).map(|i| format!("row {i}\n")).collect();
+    std::fs::write(&big, content).unwrap();
+    let out = run("tail", &["-n", "2", big.to_str().unwrap()]);
+    assert_exit_success(&out);
+    assert_stdout(&out, "row 49999\nrow 50000\n");
+
+    let bom = dir.path().join("bom.txt");
+    std::fs::write(&bom, b"\xEF\xBB\xBFonly\n").unwrap();
+    let out = run("tail", &["-c", "100", bom.to_str().unwrap()]);
+    assert_exit_success(&out);
+    assert_stdout(&out, "only\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupt_while_reading_stdin() {
+    use std::process::{Command, Stdio};
+    use std::time::Duration;
+
+    let mut child = Command::new(bin_path("tail"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdin = child.stdin.take().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    send_signal(&child, "INT");
+    let out = child.wait_with_output().unwrap();
+    drop(stdin);
+    assert_exit_code(&out, 130);
+}
+
+#[test]
+fn header_options() {
+    let f5 = fixture("five-lines.txt").to_str().unwrap().to_string();
+    let words = fixture("words.txt").to_str().unwrap().to_string();
+
+    let out = run("tail", &["-q", "-n", "1", &f5, &words]);
+    assert_exit_success(&out);
+    assert!(!stdout_str(&out).contains("==>"));
+
+    let out = run("tail", &["-v", "-n", "1", &f5]);
+    assert_exit_success(&out);
+    assert_stdout_contains(&out, &format!("==> {f5} <==\n"));
+
+    let out = run_with_stdin("tail", &["-v", "-n", "1"], b"x\n");
+    assert_exit_success(&out);
+    assert_stdout(&out, "==> standard input <==\nx\n");
+
+    let out = run(
+        "tail",
+        &["--header-format", "## {name}", "-n", "1", &f5, &words],
+    );
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(s.starts_with(&format!("## {f5}\n")), "got: {s:?}");
+    assert!(s.contains(&format!("\n\n## {words}\n")), "got: {s:?}");
+
+    let out = run("tail", &["--color=always", "-v", "-n", "1", &f5]);
+    assert_exit_success(&out);
+    assert_stdout_contains(&out, "\x1b[1m==> ");
+}
+
+#[test]
+fn files_from_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = fixture_list(dir.path(), &["five-lines.txt", "words.txt"], "\0");
+    let out = run(
+        "tail",
+        &["-q", "-n", "1", "--files0-from", list.to_str().unwrap()],
+    );
+    assert_exit_success(&out);
+    assert_stdout(&out, "five\nthe lazy dog\n");
+}
@@ -53,3 +53,58 @@ fn append_mode() {
     assert_exit_success(&out);
     assert_eq!(fs::read_to_string(&new_path).unwrap(), "created\n");
 }
+
+#[cfg(unix)]
+fn spawn_tee(args: &[&str]) -> std::process::Child {
+    use std::process::{Command, Stdio};
+    Command::new(bin_path("tee"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupt_keeps_written_data() {
+    use std::io::Write;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.txt");
+    let mut child = spawn_tee(&[path.to_str().unwrap()]);
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"before\n").unwrap();
+    stdin.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    send_signal(&child, "INT");
+    let out = child.wait_with_output().unwrap();
+    drop(stdin);
+    assert_exit_code(&out, 130);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "before\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn ignore_interrupts() {
+    use std::io::Write;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.txt");
+    let mut child = spawn_tee(&["-i", path.to_str().unwrap()]);
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"one\n").unwrap();
+    stdin.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    send_signal(&child, "INT");
+    std::thread::sleep(Duration::from_millis(100));
+    stdin.write_all(b"two\n").unwrap();
+    drop(stdin);
+    let out = child.wait_with_output().unwrap();
+    assert_exit_success(&out);
+    assert_stdout(&out, "one\ntwo\n");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+}
@@ -86,3 +86,30 @@ fn missing_file() {
     assert!(!out.status.success());
     assert_stderr_contains(&out, "wc:");
 }
+
+#[test]
+fn files_from_lists() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = fixture_list(dir.path(), &["five-lines.txt", "words.txt"], "\r\n");
+    let out = run("wc", &["-l", &format!("--files-from={}", list.display())]);
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(s.contains("five-lines.txt") && s.contains("words.txt"));
+    assert!(s.contains("8 total"), "got: {s:?}");
+
+    let list0 = fixture_list(dir.path(), &["five-lines.txt"], "\0");
+    let out = run("wc", &["-l", "--files0-from", list0.to_str().unwrap()]);
+    assert_exit_success(&out);
+    assert_stdout_contains(&out, "five-lines.txt");
+
+    let names = format!("{}\n", fixture("words.txt").display());
+    let out = run_with_stdin("wc", &["-w", "--files-from=-"], names.as_bytes());
+    assert_exit_success(&out);
+    assert_stdout_contains(&out, "9");
+
+    let empty = dir.path().join("empty.lst");
+    std::fs::write(&empty, "").unwrap();
+    let out = run("wc", &["--files-from", empty.to_str().unwrap()]);
+    assert_exit_success(&out);
+    assert_stdout(&out, "");
+}
@@ -0,0 +1,63 @@
+mod helpers;
+
+use helpers::*;
+use std::process::Command;
+
+#[test]
+fn help_version() {
+    check_help_version("wct");
+}
+
+#[test]
+fn list_tools() {
+    let out = run("wct", &["list"]);
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    for tool in ["head", "tail", "grep", "which", "yes"] {
+        assert!(s.contains(tool), "missing {tool} in: {s:?}");
+    }
+}
+
+#[test]
+fn doctor_reports_winning_copies() {
+    let bin_dir = bin_path("wct").parent().unwrap().to_path_buf();
+    let dir = tempfile::tempdir().unwrap();
+    let out = Command::new(bin_path("wct"))
+        .arg("doctor")
+        .current_dir(dir.path())
+        .env("PATH", &bin_dir)
+        .output()
+        .unwrap();
+    assert_exit_success(&out);
+    let s = stdout_str(&out);
+    assert!(s.contains("ok"), "expected ok rows in: {s:?}");
+    assert!(!s.contains("missing"), "unexpected missing tool in: {s:?}");
+}
+
+#[test]
+fn doctor_profile_snippet() {
+    // Without PowerShell on PATH no aliases are found, so there is nothing
+    // to remove.
+    let dir = tempfile::tempdir().unwrap();
+    let out = Command::new(bin_path("wct"))
+        .args(["doctor", "--profile"])
+        .env("PATH", dir.path())
+        .output()
+        .unwrap();
+    assert_exit_success(&out);
+    assert_stdout(&out, "");
+}
+
+#[test]
+fn profile_only_with_doctor() {
+    let out = run("wct", &["list", "--profile"]);
+    assert_exit_code(&out, 1);
+    assert_stderr_contains(&out, "--profile is only valid with 'doctor'");
+}
+
+#[test]
+fn unknown_command() {
+    let out = run("wct", &["bogus"]);
+    assert_exit_code(&out, 1);
+    assert_stderr_contains(&out, "wct:");
+}
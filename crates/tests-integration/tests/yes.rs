@@ -25,3 +25,28 @@ fn output_variations() {
         assert_eq!(s, *expected, "[{name}] output mismatch");
     }
 }
+
+#[cfg(unix)]
+#[test]
+fn interrupt_exit_status() {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+    use std::time::Duration;
+
+    for (sig, code) in [("INT", 130), ("TERM", 143)] {
+        let mut child = Command::new(bin_path("yes"))
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+        let drain = std::thread::spawn(move || {
+            let mut sink = Vec::new();
+            let _ = stdout.read_to_end(&mut sink);
+        });
+        std::thread::sleep(Duration::from_millis(300));
+        send_signal(&child, sig);
+        let status = child.wait().unwrap();
+        drain.join().unwrap();
+        assert_eq!(status.code(), Some(code), "[{sig}] exit status");
+    }
+}
@@ -1,6 +1,8 @@
 use common::cli;
 use common::encoding;
 use common::error;
+use common::filelist::{self, Delimiter};
+use common::input;
 use common::lexopt;
 use common::lexopt::prelude::*;
 use std::io::{self, Read, Write};
@@ -48,29 +50,61 @@ struct Counts {
     chars: usize,
 }
 
-fn count(mut reader: impl Read) -> io::Result<Counts> {
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-
-    let mut c = Counts {
-        bytes: buf.len(),
-        ..Default::default()
-    };
+fn count_chars(bytes: &[u8]) -> (usize, usize) {
+    let mut chars = 0;
+    let mut rest = bytes;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(s) => return (chars + s.chars().count(), 0),
+            Err(e) => {
+                let valid = e.valid_up_to();
+                chars += std::str::from_utf8(&rest[..valid]).map_or(0, |s| s.chars().count());
+                match e.error_len() {
+                    Some(k) => {
+                        chars += 1;
+                        rest = &rest[valid + k..];
+                    }
+                    None => return (chars, rest.len() - valid),
+                }
+            }
+        }
+    }
+}
 
+fn count(mut reader: impl Read) -> io::Result<Counts> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut carry = 0;
+    let mut c = Counts::default();
     let mut in_word = false;
-    for &b in &buf {
-        if b == b'\n' {
-            c.lines += 1;
-        }
-        let is_ws = b == b' ' || b == b'\t' || b == b'\n' || b == b'\r';
-        if !is_ws && !in_word {
-            c.words += 1;
+
+    loop {
+        let n = match reader.read(&mut buf[carry..]) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        c.bytes += n;
+        for &b in &buf[carry..carry + n] {
+            if b == b'\n' {
+                c.lines += 1;
+            }
+            let is_ws = b == b' ' || b == b'\t' || b == b'\n' || b == b'\r';
+            if !is_ws && !in_word {
+                c.words += 1;
+            }
+            in_word = !is_ws;
         }
-        in_word = !is_ws;
+        let filled = carry + n;
+        let (chars, incomplete) = count_chars(&buf[..filled]);
+        c.chars += chars;
+        buf.copy_within(filled - incomplete..filled, 0);
+        carry = incomplete;
     }
 
-    let text = String::from_utf8_lossy(&buf);
-    c.chars = text.chars().count();
+    if carry > 0 {
+        c.chars += 1;
+    }
 
     Ok(c)
 }
@@ -92,9 +126,10 @@ fn format_counts(counts: &Counts, flags: &Flags, width: usize) -> String {
     parts.join(" ")
 }
 
-fn parse_args() -> (Flags, Vec<String>) {
+fn parse_args() -> (Flags, Vec<String>, bool) {
     let mut flags = Flags::default();
     let mut files = Vec::new();
+    let mut from_list = false;
     let mut parser = lexopt::Parser::from_env();
 
     while let Some(arg) = parser
@@ -106,6 +141,14 @@ fn parse_args() -> (Flags, Vec<String>) {
             Short('w') => flags.words = true,
             Short('c') => flags.bytes = true,
             Short('m') => flags.chars = true,
+            Long("files-from") => {
+                files.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Newline));
+                from_list = true;
+            }
+            Long("files0-from") => {
+                files.extend(filelist::from_arg(TOOL, &mut parser, Delimiter::Nul));
+                from_list = true;
+            }
             Long("help") => {
                 cli::print_help(TOOL, "print newline, word, and byte counts for each file");
                 std::process::exit(0);
@@ -124,11 +167,11 @@ fn parse_args() -> (Flags, Vec<String>) {
         }
     }
 
-    (flags, files)
+    (flags, files, from_list)
 }
 
 fn run() -> io::Result<()> {
-    let (raw_flags, files) = parse_args();
+    let (raw_flags, files, from_list) = parse_args();
     let flags = raw_flags.default_mode();
     let stdout = io::stdout();
     let mut out = stdout.lock();
@@ -136,15 +179,17 @@ fn run() -> io::Result<()> {
     let mut total = Counts::default();
     let mut results: Vec<(Counts, Option<String>)> = Vec::new();
 
-    if files.is_empty() {
+    if files.is_empty() && !from_list {
         let reader = encoding::open_stdin()?;
         let c = count(reader)?;
         results.push((c, None));
     } else {
         for path in &files {
-            let reader = encoding::open_input(path).unwrap_or_else(|e| {
-                error::err(TOOL, &format!("{path}: {e}"));
-            });
+            let reader = input::open(path)
+                .and_then(|i| i.into_reader())
+                .unwrap_or_else(|e| {
+                    error::err(TOOL, &format!("{path}: {e}"));
+                });
             let c = count(reader)?;
             results.push((c, Some(path.clone())));
         }
@@ -224,6 +269,21 @@ mod tests {
         assert_eq!(c.chars, 6); // c-a-f-e-combining-\n
     }
 
+    #[test]
+    fn word_spanning_chunks() {
+        let input = format!("{}ab cd\n", " ".repeat(64 * 1024 - 1));
+        let c = count(Cursor::new(input.as_bytes())).unwrap();
+        assert_eq!(c.words, 2);
+        assert_eq!(c.lines, 1);
+    }
+
+    #[test]
+    fn multibyte_char_spanning_chunks() {
+        let input = format!("{}\u{e9}\n", "a".repeat(64 * 1024 - 1));
+        let c = count(Cursor::new(input.as_bytes())).unwrap();
+        assert_eq!(c.chars, 64 * 1024 + 1);
+    }
+
     #[test]
     fn format_default_flags() {
         let flags = Flags {
@@ -0,0 +1,345 @@
+use lexopt::prelude::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as Process;
+use which::find_command;
+
+const TOOL: &str = "wct";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const TOOLS: &[(&str, &str)] = &[
+    ("head", "Print the first lines/bytes of a file"),
+    ("tail", "Print the last lines/bytes of a file"),
+    ("wc", "Count lines, words, bytes, characters"),
+    ("cat", "Concatenate files to stdout"),
+    ("tac", "Print files in reverse line order"),
+    ("grep", "Search files for regex patterns"),
+    ("tee", "Copy stdin to stdout and files"),
+    ("touch", "Create files or update timestamps"),
+    ("which", "Locate executables on PATH"),
+    ("yes", "Repeatedly output a string"),
+];
+
+const SHELLS: &[&str] = &["powershell", "pwsh"];
+
+enum Command {
+    List,
+    Doctor { profile: bool },
+}
+
+fn parse_args() -> Command {
+    let mut parser = lexopt::Parser::from_env();
+    let mut command: Option<String> = None;
+    let mut profile = false;
+
+    while let Some(arg) = parser.next().unwrap_or_else(|e| {
+        eprintln!("{TOOL}: {e}");
+        std::process::exit(1);
+    }) {
+        match arg {
+            Long("profile") => profile = true,
+            Long("help") => {
+                println!("{TOOL} - {USAGE}");
+                std::process::exit(0);
+            }
+            Long("version") => {
+                println!("{TOOL} {VERSION}");
+                std::process::exit(0);
+            }
+            Value(v) if command.is_none() => {
+                command = Some(v.into_string().unwrap_or_else(|_| {
+                    eprintln!("{TOOL}: invalid UTF-8 in argument");
+                    std::process::exit(1);
+                }));
+            }
+            _ => {
+                eprintln!("{TOOL}: unexpected argument: {arg:?}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    match command.as_deref() {
+        Some("list") if profile => {
+            eprintln!("{TOOL}: --profile is only valid with 'doctor'");
+            std::process::exit(1);
+        }
+        Some("list") => Command::List,
+        Some("doctor") => Command::Doctor { profile },
+        Some(other) => {
+            eprintln!("{TOOL}: unknown command: {other}");
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("{TOOL}: missing command (try 'wct list' or 'wct doctor')");
+            std::process::exit(1);
+        }
+    }
+}
+
+const USAGE: &str = "\
+Usage: wct COMMAND [OPTIONS]
+
+Describe and check the windows-cli-tools installation.
+
+Commands:
+  list          List the tools in this collection
+  doctor        Show which copy of each tool wins on PATH and
+                report PowerShell aliases that shadow them
+
+Options:
+      --profile Print only the PowerShell profile snippet (doctor)
+      --help    Show this help
+      --version Show version";
+
+#[derive(Debug, PartialEq)]
+enum Status {
+    Ok(PathBuf),
+    Shadowed { winner: PathBuf, ours: PathBuf },
+    Other(PathBuf),
+    Missing,
+}
+
+fn same_dir(file: &Path, dir: &Path) -> bool {
+    let Some(parent) = file.parent() else {
+        return false;
+    };
+    match (fs::canonicalize(parent), fs::canonicalize(dir)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => parent == dir,
+    }
+}
+
+fn diagnose(found: &[PathBuf], own_dir: Option<&Path>) -> Status {
+    let Some(winner) = found.first() else {
+        return Status::Missing;
+    };
+    let Some(own_dir) = own_dir else {
+        return Status::Ok(winner.clone());
+    };
+    if same_dir(winner, own_dir) {
+        return Status::Ok(winner.clone());
+    }
+    match found.iter().find(|p| same_dir(p, own_dir)) {
+        Some(ours) => Status::Shadowed {
+            winner: winner.clone(),
+            ours: ours.clone(),
+        },
+        None => Status::Other(winner.clone()),
+    }
+}
+
+fn parse_aliases(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, target) = line.trim().split_once('=')?;
+            let name = name.trim();
+            TOOLS
+                .iter()
+                .any(|(t, _)| t.eq_ignore_ascii_case(name))
+                .then(|| (name.to_lowercase(), target.trim().to_string()))
+        })
+        .collect()
+}
+
+fn query_aliases(shell: &Path) -> Vec<(String, String)> {
+    let names: Vec<&str> = TOOLS.iter().map(|(t, _)| *t).collect();
+    let script = format!(
+        "Get-Alias -Name {} -ErrorAction SilentlyContinue | \
+         ForEach-Object {{ $_.Name + '=' + $_.Definition }}",
+        names.join(",")
+    );
+    match Process::new(shell)
+        .args(["-NoLogo", "-NonInteractive", "-Command", &script])
+        .output()
+    {
+        Ok(out) => parse_aliases(&String::from_utf8_lossy(&out.stdout)),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The aliases shadowing these tools in each PowerShell found on PATH.
+fn shell_aliases() -> Vec<(&'static str, Vec<(String, String)>)> {
+    SHELLS
+        .iter()
+        .filter_map(|shell| {
+            let path = find_command(shell, false).into_iter().next()?;
+            let aliases = query_aliases(&path);
+            (!aliases.is_empty()).then_some((*shell, aliases))
+        })
+        .collect()
+}
+
+/// The alias names in `found`, once each.
+fn alias_names<'a>(found: &'a [(&str, Vec<(String, String)>)]) -> Vec<&'a str> {
+    let mut names: Vec<&str> = Vec::new();
+    for (name, _) in found.iter().flat_map(|(_, aliases)| aliases) {
+        if !names.contains(&name.as_str()) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn profile_snippet(names: &[&str]) -> String {
+    let mut s =
+        String::from("# windows-cli-tools: use the native executables instead of aliases\n");
+    for name in names {
+        s.push_str(&format!(
+            "Remove-Item Alias:{name} -Force -ErrorAction SilentlyContinue\n"
+        ));
+    }
+    s
+}
+
+fn list() {
+    let width = TOOLS.iter().map(|(t, _)| t.len()).max().unwrap_or(0);
+    for (name, desc) in TOOLS {
+        println!("{name:<width$}  {desc}");
+    }
+}
+
+fn doctor() -> i32 {
+    let own_dir = env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf));
+    let mut problems = 0;
+
+    match &own_dir {
+        Some(d) => println!("Tools directory: {}", d.display()),
+        None => println!("Tools directory: unknown"),
+    }
+    println!();
+
+    let width = TOOLS.iter().map(|(t, _)| t.len()).max().unwrap_or(0);
+    for (name, _) in TOOLS {
+        let found = find_command(name, true);
+        let status = diagnose(&found, own_dir.as_deref());
+        if !matches!(status, Status::Ok(_)) {
+            problems += 1;
+        }
+        match status {
+            Status::Ok(p) => println!("  ok        {name:<width$}  {}", p.display()),
+            Status::Shadowed { winner, ours } => println!(
+                "  shadowed  {name:<width$}  {} (this install: {})",
+                winner.display(),
+                ours.display()
+            ),
+            Status::Other(p) => println!("  other     {name:<width$}  {}", p.display()),
+            Status::Missing => println!("  missing   {name:<width$}"),
+        }
+    }
+
+    let found = shell_aliases();
+    for (shell, aliases) in &found {
+        println!();
+        println!("{shell} aliases that shadow these tools:");
+        for (name, target) in aliases {
+            println!("  {name} -> {target}");
+        }
+    }
+
+    let names = alias_names(&found);
+    if !names.is_empty() {
+        problems += names.len();
+        println!();
+        println!("Add this to your PowerShell profile ($PROFILE) to remove them:");
+        println!();
+        print!("{}", profile_snippet(&names));
+    }
+
+    if problems == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+fn main() {
+    let code = match parse_args() {
+        Command::List => {
+            list();
+            0
+        }
+        Command::Doctor { profile: true } => {
+            let found = shell_aliases();
+            let names = alias_names(&found);
+            if !names.is_empty() {
+                print!("{}", profile_snippet(&names));
+            }
+            0
+        }
+        Command::Doctor { profile: false } => doctor(),
+    };
+    std::process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnose_ours_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("cat.exe");
+        fs::write(&exe, "fake").unwrap();
+        let status = diagnose(std::slice::from_ref(&exe), Some(dir.path()));
+        assert_eq!(status, Status::Ok(exe));
+    }
+
+    #[test]
+    fn diagnose_shadowed() {
+        let ours_dir = tempfile::tempdir().unwrap();
+        let git_dir = tempfile::tempdir().unwrap();
+        let ours = ours_dir.path().join("cat.exe");
+        let git = git_dir.path().join("cat.exe");
+        fs::write(&ours, "fake").unwrap();
+        fs::write(&git, "fake").unwrap();
+
+        let status = diagnose(&[git.clone(), ours.clone()], Some(ours_dir.path()));
+        assert_eq!(status, Status::Shadowed { winner: git, ours });
+    }
+
+    #[test]
+    fn diagnose_other_and_missing() {
+        let ours_dir = tempfile::tempdir().unwrap();
+        let other_dir = tempfile::tempdir().unwrap();
+        let other = other_dir.path().join("grep.exe");
+        fs::write(&other, "fake").unwrap();
+
+        let status = diagnose(std::slice::from_ref(&other), Some(ours_dir.path()));
+        assert_eq!(status, Status::Other(other));
+        assert_eq!(diagnose(&[], Some(ours_dir.path())), Status::Missing);
+    }
+
+    #[test]
+    fn parse_alias_output() {
+        let output = "cat=Get-Content\r\ntee=Tee-Object\r\nls=Get-ChildItem\r\n\r\n";
+        assert_eq!(
+            parse_aliases(output),
+            vec![
+                ("cat".to_string(), "Get-Content".to_string()),
+                ("tee".to_string(), "Tee-Object".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn snippet_removes_each_alias() {
+        let s = profile_snippet(&["cat", "tee"]);
+        assert!(s.contains("Remove-Item Alias:cat -Force"));
+        assert!(s.contains("Remove-Item Alias:tee -Force"));
+    }
+
+    #[test]
+    fn alias_names_are_listed_once() {
+        let alias = |name: &str| (name.to_string(), "Get-Content".to_string());
+        let found = [
+            ("powershell", vec![alias("cat"), alias("tee")]),
+            ("pwsh", vec![alias("cat")]),
+        ];
+        assert_eq!(alias_names(&found), ["cat", "tee"]);
+    }
+}
@@ -1,3 +1,4 @@
+use common::interrupt;
 use lexopt::prelude::*;
 use std::io::{self, Write};
 
@@ -64,7 +65,7 @@ fn run() -> io::Result<()> {
     let line_bytes = line.as_bytes();
 
     let stdout = io::stdout();
-    let mut out = io::BufWriter::with_capacity(8192, stdout.lock());
+    let mut out = io::BufWriter::with_capacity(8192, interrupt::Checked(stdout.lock()));
     let mut buf = Vec::with_capacity(8192);
     fill_buffer(line_bytes, &mut buf);
 
@@ -74,7 +75,12 @@ fn run() -> io::Result<()> {
 }
 
 fn main() {
-    if let Err(e) = run() {
+    interrupt::install();
+    let result = run();
+    if interrupt::interrupted() {
+        std::process::exit(interrupt::exit_code());
+    }
+    if let Err(e) = result {
         if e.kind() != io::ErrorKind::BrokenPipe {
             eprintln!("{TOOL}: {e}");
             std::process::exit(1);